@@ -15,6 +15,34 @@ fn main() {
         .write_to_file("./src/contracts/azos_adapter_uniswap_v2.rs")
         .unwrap();
 
+    Abigen::new("AzosAdapterUniswapV3", "./abis/AzosAdapterUniswapV3.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/azos_adapter_uniswap_v3.rs")
+        .unwrap();
+
+    Abigen::new("AzosAdapterCurve", "./abis/AzosAdapterCurve.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/azos_adapter_curve.rs")
+        .unwrap();
+
+    Abigen::new("CurveStableSwap", "./abis/CurveStableSwap.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/curve_stable_swap.rs")
+        .unwrap();
+
+    Abigen::new("ERC20", "./abis/ERC20.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/erc20.rs")
+        .unwrap();
+
     Abigen::new("UniswapV2Router02", "./abis/UniswapV2Router02.json")
         .unwrap()
         .generate()
@@ -35,4 +63,25 @@ fn main() {
         .unwrap()
         .write_to_file("./src/contracts/uniswap_v2_pair.rs")
         .unwrap();
+
+    Abigen::new("UniswapV3Factory", "./abis/UniswapV3Factory.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/uniswap_v3_factory.rs")
+        .unwrap();
+
+    Abigen::new("UniswapV3Pool", "./abis/UniswapV3Pool.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/uniswap_v3_pool.rs")
+        .unwrap();
+
+    Abigen::new("UniswapV3QuoterV2", "./abis/UniswapV3QuoterV2.json")
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file("./src/contracts/uniswap_v3_quoter_v2.rs")
+        .unwrap();
 }
@@ -0,0 +1,230 @@
+pub mod curve;
+pub mod uniswap_v2;
+pub mod uniswap_v3;
+
+use crate::config::{AdapterConfig, AdapterKind};
+use crate::types::provider::StabilityModule;
+use crate::types::swap::SwapDetails;
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::Address;
+use ethers::providers::Middleware;
+use ethers::types::{Bytes, U256};
+use ethers::utils::format_bytes32_string;
+use std::collections::HashMap;
+use tracing::info;
+
+/// An Azos adapter the stability module delegate calls into to perform a swap
+pub trait Adapter {
+    /// The name the adapter is registered under on the stability module
+    fn name(&self) -> &str;
+
+    fn address(&self) -> Address;
+
+    /// Calldata for the adapter's `swap(bytes)`, which the stability module passes through as `data`
+    fn encode_swap_data(&self, swap_details: &SwapDetails, deadline: U256) -> Result<Bytes>;
+
+    fn name_as_bytes32(&self) -> [u8; 32] {
+        format_bytes32_string(self.name()).unwrap()
+    }
+}
+
+/// Adapters keyed by their bytes32 registration name
+#[derive(Default)]
+pub struct AdapterRegistry {
+    adapters: HashMap<[u8; 32], Box<dyn Adapter>>,
+}
+
+impl AdapterRegistry {
+    pub fn from_config(adapter_configs: &[AdapterConfig]) -> Result<Self> {
+        let mut registry = AdapterRegistry::default();
+        for adapter_config in adapter_configs {
+            let adapter: Box<dyn Adapter> = match adapter_config.kind {
+                AdapterKind::UniswapV2 { router_address } => {
+                    Box::new(uniswap_v2::UniswapV2Adapter {
+                        name: adapter_config.name.clone(),
+                        address: adapter_config.address,
+                        router_address,
+                    })
+                }
+                AdapterKind::UniswapV3 { router_address } => {
+                    Box::new(uniswap_v3::UniswapV3Adapter {
+                        name: adapter_config.name.clone(),
+                        address: adapter_config.address,
+                        router_address,
+                    })
+                }
+                AdapterKind::Curve => Box::new(curve::CurveAdapter {
+                    name: adapter_config.name.clone(),
+                    address: adapter_config.address,
+                }),
+            };
+            registry.register(adapter)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, adapter: Box<dyn Adapter>) -> Result<()> {
+        let name = format_bytes32_string(adapter.name())
+            .map_err(|_| anyhow!("Adapter name {} is longer than 31 bytes", adapter.name()))?;
+        if self.adapters.contains_key(&name) {
+            bail!("Adapter {} is registered more than once", adapter.name());
+        }
+        self.adapters.insert(name, adapter);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Adapter> {
+        let name = format_bytes32_string(name).ok()?;
+        self.adapters.get(&name).map(|adapter| adapter.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Adapter> {
+        self.adapters.values().map(|adapter| adapter.as_ref())
+    }
+
+    /**
+     * Confirms every adapter is registered on the stability module, under its name, at the configured address.
+     */
+    pub async fn verify<M: Middleware + 'static>(
+        &self,
+        stability_module: &StabilityModule<M>,
+    ) -> Result<()> {
+        for adapter in self.iter() {
+            let registered_address = stability_module
+                .get_adapter(adapter.name_as_bytes32())
+                .call()
+                .await?;
+            if registered_address == Address::zero() {
+                bail!(
+                    "Adapter {} is not registered on the stability module",
+                    adapter.name()
+                );
+            }
+            if registered_address != adapter.address() {
+                bail!(
+                    "Adapter {} is registered at {registered_address:?}, but {:?} is configured",
+                    adapter.name(),
+                    adapter.address()
+                );
+            }
+            info!(
+                adapter = adapter.name(),
+                address = ?registered_address,
+                "Adapter verified"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::token::Token;
+    use crate::types::venue::Venue;
+    use ethers::utils::id;
+    use rust_decimal::Decimal;
+
+    fn registry() -> AdapterRegistry {
+        AdapterRegistry::from_config(&[
+            AdapterConfig {
+                name: String::from("USDC"),
+                address: Address::repeat_byte(1),
+                kind: AdapterKind::UniswapV2 {
+                    router_address: Address::repeat_byte(2),
+                },
+            },
+            AdapterConfig {
+                name: String::from("USDC_V3"),
+                address: Address::repeat_byte(4),
+                kind: AdapterKind::UniswapV3 {
+                    router_address: Address::repeat_byte(5),
+                },
+            },
+            AdapterConfig {
+                name: String::from("USDC_CURVE"),
+                address: Address::repeat_byte(3),
+                kind: AdapterKind::Curve,
+            },
+        ])
+        .unwrap()
+    }
+
+    fn swap_details(venue: Venue) -> SwapDetails {
+        let token = |byte: u8, symbol: &str| Token {
+            symbol: String::from(symbol),
+            address: Address::repeat_byte(byte),
+            decimals: 18,
+        };
+        SwapDetails {
+            dex_price: Decimal::ONE,
+            venue,
+            adapter_name: String::from("USDC"),
+            token_to_sell: token(10, "ZAI"),
+            amount_to_sell: Decimal::ONE,
+            token_to_buy: token(11, "USDC"),
+            amount_to_buy_min: Decimal::ONE,
+            path: vec![Address::repeat_byte(10), Address::repeat_byte(11)],
+        }
+    }
+
+    #[test]
+    fn adapters_are_found_by_name() {
+        let registry = registry();
+        assert_eq!(
+            registry.get("USDC_CURVE").unwrap().address(),
+            Address::repeat_byte(3)
+        );
+        assert!(registry.get("USDC_V2").is_none());
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let mut registry = registry();
+        let duplicate = curve::CurveAdapter {
+            name: String::from("USDC"),
+            address: Address::repeat_byte(4),
+        };
+        assert!(registry.register(Box::new(duplicate)).is_err());
+    }
+
+    #[test]
+    fn swap_data_calls_the_adapter_swap_function() {
+        let registry = registry();
+        let data = registry
+            .get("USDC")
+            .unwrap()
+            .encode_swap_data(&swap_details(Venue::UniswapV2), U256::from(100))
+            .unwrap();
+        assert_eq!(data[..4], id("swap(bytes)"));
+    }
+
+    #[test]
+    fn adapters_refuse_other_venues() {
+        let registry = registry();
+        let uniswap_v2 = Venue::UniswapV2;
+        let uniswap_v3 = Venue::UniswapV3 { fee: 500 };
+        let curve = Venue::CurveStableSwap {
+            pool: Address::repeat_byte(5),
+            coins: [Address::repeat_byte(10), Address::repeat_byte(11)],
+        };
+        let cases = [
+            ("USDC", &uniswap_v3, "Uniswap V2 pairs"),
+            ("USDC", &curve, "Uniswap V2 pairs"),
+            ("USDC_V3", &uniswap_v2, "Uniswap V3 pools"),
+            ("USDC_V3", &curve, "Uniswap V3 pools"),
+            ("USDC_CURVE", &uniswap_v2, "Curve pools"),
+            ("USDC_CURVE", &uniswap_v3, "Curve pools"),
+        ];
+        for (adapter_name, venue, expected) in cases {
+            let result = registry
+                .get(adapter_name)
+                .unwrap()
+                .encode_swap_data(&swap_details(venue.clone()), U256::from(100));
+            assert!(
+                result.is_err_and(|error| error.to_string().contains(expected)),
+                "{adapter_name} swapped on {venue:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::Adapter;
+use crate::contracts::azos_adapter_curve::SwapCall;
+use crate::types::swap::SwapDetails;
+use crate::types::token::Token;
+use crate::types::venue::Venue;
+use crate::utils::decimal::decimal_to_u256;
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::{encode, AbiEncode, Address, Token as EthersToken};
+use ethers::types::{Bytes, U256};
+use tracing::debug;
+
+pub struct CurveAdapter {
+    pub name: String,
+    pub address: Address,
+}
+
+fn coin_index(coins: &[Address; 2], token: &Token) -> Result<usize> {
+    coins
+        .iter()
+        .position(|coin| *coin == token.address)
+        .ok_or_else(|| anyhow!("{} is not a coin of the Curve pool", token.symbol))
+}
+
+impl Adapter for CurveAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    /**
+     * abi.encode(dx, minDy, i, j, pool, deadline)
+     */
+    fn encode_swap_data(&self, swap_details: &SwapDetails, deadline: U256) -> Result<Bytes> {
+        let Venue::CurveStableSwap { pool, coins } = &swap_details.venue else {
+            bail!("{} can only swap on Curve pools", self.name);
+        };
+        let i = coin_index(coins, &swap_details.token_to_sell)?;
+        let j = coin_index(coins, &swap_details.token_to_buy)?;
+        let adapter_swap_args = vec![
+            EthersToken::Uint(decimal_to_u256(
+                swap_details.amount_to_sell,
+                swap_details.token_to_sell.decimals,
+            )),
+            EthersToken::Uint(decimal_to_u256(
+                swap_details.amount_to_buy_min,
+                swap_details.token_to_buy.decimals,
+            )),
+            EthersToken::Int(U256::from(i)),
+            EthersToken::Int(U256::from(j)),
+            EthersToken::Address(*pool),
+            EthersToken::Uint(deadline),
+        ];
+        debug!(?adapter_swap_args, "Adapter swap arguments");
+        let data: Bytes = encode(&adapter_swap_args).into();
+        Ok(SwapCall { data }.encode().into())
+    }
+}
@@ -0,0 +1,59 @@
+use super::Adapter;
+use crate::contracts::azos_adapter_uniswap_v2::SwapCall;
+use crate::types::swap::SwapDetails;
+use crate::types::venue::Venue;
+use crate::utils::decimal::decimal_to_u256;
+use anyhow::{bail, Result};
+use ethers::abi::{encode, AbiEncode, Address, Token as EthersToken};
+use ethers::types::{Bytes, U256};
+use tracing::debug;
+
+pub struct UniswapV2Adapter {
+    pub name: String,
+    pub address: Address,
+    pub router_address: Address,
+}
+
+impl Adapter for UniswapV2Adapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    /**
+     * abi.encode(amountIn, amountOutMin, path, deadline, router)
+     */
+    fn encode_swap_data(&self, swap_details: &SwapDetails, deadline: U256) -> Result<Bytes> {
+        let Venue::UniswapV2 = swap_details.venue else {
+            bail!("{} can only swap on Uniswap V2 pairs", self.name);
+        };
+        if swap_details.path.len() < 2 {
+            bail!("Uniswap V2 swaps need a path of at least two tokens");
+        }
+        let adapter_swap_args = vec![
+            EthersToken::Uint(decimal_to_u256(
+                swap_details.amount_to_sell,
+                swap_details.token_to_sell.decimals,
+            )), // ZAI
+            EthersToken::Uint(decimal_to_u256(
+                swap_details.amount_to_buy_min,
+                swap_details.token_to_buy.decimals,
+            )), // USDC
+            EthersToken::Array(
+                swap_details
+                    .path
+                    .iter()
+                    .map(|a| EthersToken::Address(*a))
+                    .collect(),
+            ), // Path
+            EthersToken::Uint(deadline),               // Deadline
+            EthersToken::Address(self.router_address), // Router
+        ];
+        debug!(?adapter_swap_args, "Adapter swap arguments");
+        let data: Bytes = encode(&adapter_swap_args).into();
+        Ok(SwapCall { data }.encode().into())
+    }
+}
@@ -0,0 +1,52 @@
+use super::Adapter;
+use crate::contracts::azos_adapter_uniswap_v3::SwapCall;
+use crate::types::swap::SwapDetails;
+use crate::types::venue::Venue;
+use crate::utils::decimal::decimal_to_u256;
+use anyhow::{bail, Result};
+use ethers::abi::{encode, AbiEncode, Address, Token as EthersToken};
+use ethers::types::{Bytes, U256};
+use tracing::debug;
+
+pub struct UniswapV3Adapter {
+    pub name: String,
+    pub address: Address,
+    pub router_address: Address,
+}
+
+impl Adapter for UniswapV3Adapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    /**
+     * An exact input single pool swap, abi.encode(amountIn, amountOutMinimum, tokenIn, tokenOut, fee, deadline, router)
+     */
+    fn encode_swap_data(&self, swap_details: &SwapDetails, deadline: U256) -> Result<Bytes> {
+        let Venue::UniswapV3 { fee } = swap_details.venue else {
+            bail!("{} can only swap on Uniswap V3 pools", self.name);
+        };
+        let adapter_swap_args = vec![
+            EthersToken::Uint(decimal_to_u256(
+                swap_details.amount_to_sell,
+                swap_details.token_to_sell.decimals,
+            )),
+            EthersToken::Uint(decimal_to_u256(
+                swap_details.amount_to_buy_min,
+                swap_details.token_to_buy.decimals,
+            )),
+            EthersToken::Address(swap_details.token_to_sell.address),
+            EthersToken::Address(swap_details.token_to_buy.address),
+            EthersToken::Uint(U256::from(fee)),
+            EthersToken::Uint(deadline),
+            EthersToken::Address(self.router_address),
+        ];
+        debug!(?adapter_swap_args, "Adapter swap arguments");
+        let data: Bytes = encode(&adapter_swap_args).into();
+        Ok(SwapCall { data }.encode().into())
+    }
+}
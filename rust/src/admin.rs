@@ -0,0 +1,394 @@
+use crate::config;
+use crate::contracts::azos_stability_module::{
+    AddAdapterCall, AddAuthorizationCall, AzosStabilityModuleErrors, ChangeBasisFeeCall,
+    ChangeDebtCeilingCall, ChangeMaxDepositCall, ChangeTreasuryCall, DepositCall,
+    RemoveAuthorizationCall, WindDownCall,
+};
+use crate::keeper;
+use crate::rpc::FailoverClient;
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, Subcommand};
+use ethers::abi::AbiEncode;
+use ethers::contract::ContractRevert;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::{format_bytes32_string, to_checksum};
+use serde_json::{json, Value};
+use std::fmt;
+use std::io::Write;
+use std::time::SystemTime;
+use tracing::info;
+
+/// A governance or treasury call on the stability module, amounts in the module's own base units
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdminOperation {
+    AddAdapter { name: String, address: Address },
+    ChangeBasisFee(U256),
+    ChangeDebtCeiling(U256),
+    ChangeMaxDeposit(U256),
+    ChangeTreasury(Address),
+    Deposit(U256),
+    AddAuthorization(Address),
+    RemoveAuthorization(Address),
+    WindDown,
+}
+
+impl fmt::Display for AdminOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminOperation::AddAdapter { name, address } => {
+                write!(f, "addAdapter({name}, {address:?})")
+            }
+            AdminOperation::ChangeBasisFee(basis_fee) => write!(f, "changeBasisFee({basis_fee})"),
+            AdminOperation::ChangeDebtCeiling(debt_ceiling) => {
+                write!(f, "changeDebtCeiling({debt_ceiling})")
+            }
+            AdminOperation::ChangeMaxDeposit(max_deposit) => {
+                write!(f, "changeMaxDeposit({max_deposit})")
+            }
+            AdminOperation::ChangeTreasury(treasury) => write!(f, "changeTreasury({treasury:?})"),
+            AdminOperation::Deposit(amount) => write!(f, "deposit({amount})"),
+            AdminOperation::AddAuthorization(account) => {
+                write!(f, "addAuthorization({account:?})")
+            }
+            AdminOperation::RemoveAuthorization(account) => {
+                write!(f, "removeAuthorization({account:?})")
+            }
+            AdminOperation::WindDown => write!(f, "windDown()"),
+        }
+    }
+}
+
+impl AdminOperation {
+    pub fn calldata(&self) -> Result<Bytes> {
+        let calldata = match self {
+            AdminOperation::AddAdapter { name, address } => AddAdapterCall {
+                adapter_name: format_bytes32_string(name)
+                    .map_err(|_| anyhow!("Adapter name {name} is longer than 31 bytes"))?,
+                adapter: *address,
+            }
+            .encode(),
+            AdminOperation::ChangeBasisFee(new_basis_fee) => ChangeBasisFeeCall {
+                new_basis_fee: *new_basis_fee,
+            }
+            .encode(),
+            AdminOperation::ChangeDebtCeiling(new_debt_ceiling) => ChangeDebtCeilingCall {
+                new_debt_ceiling: *new_debt_ceiling,
+            }
+            .encode(),
+            AdminOperation::ChangeMaxDeposit(new_max_deposit) => ChangeMaxDepositCall {
+                new_max_deposit: *new_max_deposit,
+            }
+            .encode(),
+            AdminOperation::ChangeTreasury(new_treasury) => ChangeTreasuryCall {
+                new_treasury: *new_treasury,
+            }
+            .encode(),
+            AdminOperation::Deposit(amount) => DepositCall { amount: *amount }.encode(),
+            AdminOperation::AddAuthorization(account) => {
+                AddAuthorizationCall { account: *account }.encode()
+            }
+            AdminOperation::RemoveAuthorization(account) => {
+                RemoveAuthorizationCall { account: *account }.encode()
+            }
+            AdminOperation::WindDown => WindDownCall.encode(),
+        };
+        Ok(calldata.into())
+    }
+
+    pub fn transaction(&self, stability_module_address: Address) -> Result<TypedTransaction> {
+        Ok(TransactionRequest::new()
+            .to(stability_module_address)
+            .data(self.calldata()?)
+            .into())
+    }
+}
+
+/**
+ * A batch the Safe Transaction Builder can import, for a multisig owning the module to propose the operations.
+ */
+pub fn safe_batch(
+    chain_id: u64,
+    stability_module_address: Address,
+    operations: &[AdminOperation],
+) -> Result<Value> {
+    let transactions = operations
+        .iter()
+        .map(|operation| {
+            Ok(json!({
+                "to": to_checksum(&stability_module_address, None),
+                "value": "0",
+                "data": operation.calldata()?,
+                "contractMethod": null,
+                "contractInputsValues": null,
+            }))
+        })
+        .collect::<Result<Vec<Value>>>()?;
+    let created_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let description = operations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ");
+    Ok(json!({
+        "version": "1.0",
+        "chainId": chain_id.to_string(),
+        "createdAt": created_at,
+        "meta": {
+            "name": "Azos stability module",
+            "description": description,
+        },
+        "transactions": transactions,
+    }))
+}
+
+/**
+ * Runs the operation as an `eth_call` from `from`, surfacing the module's revert reason instead of paying for it.
+ */
+pub async fn dry_run<M: Middleware>(
+    provider: &M,
+    stability_module_address: Address,
+    from: Address,
+    operation: &AdminOperation,
+) -> Result<()> {
+    let mut tx = operation.transaction(stability_module_address)?;
+    tx.set_from(from);
+    if let Err(error) = provider.call(&tx, None).await {
+        let revert_reason = error
+            .as_error_response()
+            .and_then(|response| response.as_revert_data())
+            .and_then(|data| AzosStabilityModuleErrors::decode_with_selector(&data));
+        bail!("{operation} would revert: {error}, revert reason: {revert_reason:?}");
+    }
+    Ok(())
+}
+
+/**
+ * Sends the operation from the provider's default sender and waits for `confirmations`.
+ */
+pub async fn send<M: Middleware>(
+    provider: &M,
+    stability_module_address: Address,
+    operation: &AdminOperation,
+    confirmations: usize,
+) -> Result<TransactionReceipt> {
+    let tx = operation.transaction(stability_module_address)?;
+    let pending_tx = provider
+        .send_transaction(tx, None)
+        .await
+        .map_err(|error| anyhow!("Unable to send {operation}: {error}"))?;
+    info!(%operation, tx_hash = ?pending_tx.tx_hash(), "Sent");
+    let receipt = pending_tx
+        .confirmations(confirmations)
+        .await?
+        .ok_or_else(|| anyhow!("Transaction was dropped before confirming"))?;
+    if receipt.status == Some(U64::zero()) {
+        bail!(
+            "{operation} reverted, tx_hash={:?}",
+            receipt.transaction_hash
+        );
+    }
+    Ok(receipt)
+}
+
+#[derive(Args)]
+pub struct AdminArgs {
+    #[command(subcommand)]
+    operation: AdminCommand,
+    /// Check the call against the chain from the keeper's account without sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Print a Safe Transaction Builder batch for a multisig to propose instead of sending
+    #[arg(long, global = true, conflicts_with = "dry_run")]
+    safe: bool,
+    /// Send without asking for confirmation
+    #[arg(long, short, global = true)]
+    yes: bool,
+}
+
+/// Amounts are in the module's base units, as it stores them
+#[derive(Subcommand)]
+enum AdminCommand {
+    /// Register an adapter under a name
+    AddAdapter { name: String, address: Address },
+    /// Set the fee charged on expansions and contractions
+    ChangeBasisFee {
+        #[arg(value_parser = U256::from_dec_str)]
+        basis_fee: U256,
+    },
+    /// Set how much system coin the module may have minted
+    ChangeDebtCeiling {
+        #[arg(value_parser = U256::from_dec_str)]
+        debt_ceiling: U256,
+    },
+    /// Set how much collateral the module may hold
+    ChangeMaxDeposit {
+        #[arg(value_parser = U256::from_dec_str)]
+        max_deposit: U256,
+    },
+    /// Set where the module's fees go
+    ChangeTreasury { treasury: Address },
+    /// Deposit collateral into the module, which has to be approved first
+    Deposit {
+        #[arg(value_parser = U256::from_dec_str)]
+        amount: U256,
+    },
+    /// Allow an account to act on the module
+    AddAuthorization { account: Address },
+    /// Revoke an account's access to the module
+    RemoveAuthorization { account: Address },
+    /// Stop the module for good
+    WindDown,
+}
+
+impl From<AdminCommand> for AdminOperation {
+    fn from(command: AdminCommand) -> Self {
+        match command {
+            AdminCommand::AddAdapter { name, address } => {
+                AdminOperation::AddAdapter { name, address }
+            }
+            AdminCommand::ChangeBasisFee { basis_fee } => AdminOperation::ChangeBasisFee(basis_fee),
+            AdminCommand::ChangeDebtCeiling { debt_ceiling } => {
+                AdminOperation::ChangeDebtCeiling(debt_ceiling)
+            }
+            AdminCommand::ChangeMaxDeposit { max_deposit } => {
+                AdminOperation::ChangeMaxDeposit(max_deposit)
+            }
+            AdminCommand::ChangeTreasury { treasury } => AdminOperation::ChangeTreasury(treasury),
+            AdminCommand::Deposit { amount } => AdminOperation::Deposit(amount),
+            AdminCommand::AddAuthorization { account } => AdminOperation::AddAuthorization(account),
+            AdminCommand::RemoveAuthorization { account } => {
+                AdminOperation::RemoveAuthorization(account)
+            }
+            AdminCommand::WindDown => AdminOperation::WindDown,
+        }
+    }
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/**
+ * Checks and sends the operation from the keeper's account once confirmed, or prints it as a Safe batch.  A batch only
+ * needs the endpoints and the module configured, nothing is signed for it.
+ */
+pub async fn command(args: AdminArgs) -> Result<()> {
+    let operation = AdminOperation::from(args.operation);
+    if args.safe {
+        let provider = Provider::new(FailoverClient::new(&config::generate_rpc_urls())?);
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let stability_module_address = config::generate_stability_module_address();
+        let batch = safe_batch(chain_id, stability_module_address, &[operation])?;
+        println!("{}", serde_json::to_string_pretty(&batch)?);
+        return Ok(());
+    }
+
+    let config = config::generate_config();
+    let stability_module_address = config.stability_module_address;
+    let provider = keeper::connect(&config, None)?;
+    let from = provider.address();
+    println!("{operation}");
+    println!("  to:       {stability_module_address:?}");
+    println!("  from:     {from:?}");
+    println!("  calldata: {}", operation.calldata()?);
+    dry_run(&provider, stability_module_address, from, &operation).await?;
+    if args.dry_run {
+        println!("Dry run succeeded, nothing was sent");
+        return Ok(());
+    }
+    if !args.yes && !confirm(&format!("Send {operation}?"))? {
+        println!("Aborted");
+        return Ok(());
+    }
+    let receipt = send(
+        &provider,
+        stability_module_address,
+        &operation,
+        config.tx_confirmations_required,
+    )
+    .await?;
+    println!(
+        "Confirmed in block {:?}, tx_hash={:?}",
+        receipt.block_number, receipt.transaction_hash
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::azos_stability_module::AzosStabilityModuleCalls;
+    use ethers::abi::AbiDecode;
+
+    #[test]
+    fn calldata_matches_the_module_abi() {
+        let adapter = Address::repeat_byte(0x30);
+        let calldata = AdminOperation::AddAdapter {
+            name: String::from("USDC"),
+            address: adapter,
+        }
+        .calldata()
+        .unwrap();
+        assert_eq!(
+            AzosStabilityModuleCalls::decode(&calldata).unwrap(),
+            AzosStabilityModuleCalls::AddAdapter(AddAdapterCall {
+                adapter_name: format_bytes32_string("USDC").unwrap(),
+                adapter,
+            })
+        );
+
+        let calldata = AdminOperation::ChangeDebtCeiling(U256::exp10(24))
+            .calldata()
+            .unwrap();
+        assert_eq!(
+            AzosStabilityModuleCalls::decode(&calldata).unwrap(),
+            AzosStabilityModuleCalls::ChangeDebtCeiling(ChangeDebtCeilingCall {
+                new_debt_ceiling: U256::exp10(24)
+            })
+        );
+
+        let name = "a name well over thirty one bytes long";
+        assert!(AdminOperation::AddAdapter {
+            name: String::from(name),
+            address: adapter
+        }
+        .calldata()
+        .is_err());
+    }
+
+    #[test]
+    fn safe_batches_carry_every_operation() {
+        let stability_module_address = Address::repeat_byte(0x20);
+        let operations = [
+            AdminOperation::ChangeBasisFee(U256::from(5)),
+            AdminOperation::WindDown,
+        ];
+        let batch = safe_batch(11155111, stability_module_address, &operations).unwrap();
+
+        assert_eq!(batch["chainId"], "11155111");
+        assert_eq!(
+            batch["meta"]["description"],
+            "changeBasisFee(5), windDown()"
+        );
+        let transactions = batch["transactions"].as_array().unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions[0]["to"],
+            to_checksum(&stability_module_address, None)
+        );
+        assert_eq!(transactions[0]["value"], "0");
+        let calldata: Bytes = serde_json::from_value(transactions[1]["data"].clone()).unwrap();
+        assert_eq!(
+            AzosStabilityModuleCalls::decode(&calldata).unwrap(),
+            AzosStabilityModuleCalls::WindDown(WindDownCall)
+        );
+    }
+}
@@ -0,0 +1,785 @@
+use crate::config::{self, StrategyConfig};
+use crate::contracts::uniswap_v2_factory::UniswapV2Factory;
+use crate::contracts::uniswap_v2_pair::{UniswapV2Pair, UniswapV2PairEvents};
+use crate::rpc::{FailoverClient, LOG_CHUNK_BLOCKS};
+use crate::utils::constant_product::{get_amount_in, get_amount_out, Hop};
+use crate::utils::decimal::{
+    apply_slippage_bps, decimal_is_within_allowed_range, u256_to_decimal, BPS_DENOMINATOR,
+};
+use crate::venues::uniswap_v2::direct_quantity_to_buy;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use ethers::abi::Address;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::U256;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// A `Sync` or `Swap` log of the pair, one per line in a JSONL dump
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PoolEvent {
+    pub block_number: u64,
+    pub log_index: u64,
+    #[serde(flatten)]
+    pub kind: PoolEventKind,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "event")]
+pub enum PoolEventKind {
+    Sync {
+        reserve0: U256,
+        reserve1: U256,
+    },
+    Swap {
+        amount0_in: U256,
+        amount1_in: U256,
+        amount0_out: U256,
+        amount1_out: U256,
+    },
+}
+
+/**
+ * Reads a JSONL dump of pool events, in the order they were emitted.
+ */
+pub fn load_events(path: &Path) -> Result<Vec<PoolEvent>> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Unable to open {}", path.display()))?;
+    let mut events = vec![];
+    for (index, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        events.push(
+            serde_json::from_str(&line)
+                .with_context(|| format!("Invalid pool event on line {}", index + 1))?,
+        );
+    }
+    Ok(events)
+}
+
+pub fn save_events(path: &Path, events: &[PoolEvent]) -> Result<()> {
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("Unable to create {}", path.display()))?;
+    for event in events {
+        writeln!(file, "{}", serde_json::to_string(event)?)?;
+    }
+    Ok(())
+}
+
+/**
+ * Fetches the pair's `Sync` and `Swap` logs between two blocks, inclusive.
+ */
+pub async fn fetch_events<M: Middleware + 'static>(
+    provider: Arc<M>,
+    pair_address: Address,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<PoolEvent>> {
+    let pair = UniswapV2Pair::new(pair_address, provider);
+    let mut events = vec![];
+    let mut chunk_start = from_block;
+    while chunk_start <= to_block {
+        let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(to_block);
+        debug!(
+            from_block = chunk_start,
+            to_block = chunk_end,
+            "Fetching pool logs"
+        );
+        let logs = pair
+            .events()
+            .from_block(chunk_start)
+            .to_block(chunk_end)
+            .query_with_meta()
+            .await?;
+        for (log, meta) in logs {
+            let kind = match log {
+                UniswapV2PairEvents::SyncFilter(sync) => PoolEventKind::Sync {
+                    reserve0: U256::from(sync.reserve_0),
+                    reserve1: U256::from(sync.reserve_1),
+                },
+                UniswapV2PairEvents::SwapFilter(swap) => PoolEventKind::Swap {
+                    amount0_in: swap.amount_0_in,
+                    amount1_in: swap.amount_1_in,
+                    amount0_out: swap.amount_0_out,
+                    amount1_out: swap.amount_1_out,
+                },
+                _ => continue,
+            };
+            events.push(PoolEvent {
+                block_number: meta.block_number.as_u64(),
+                log_index: meta.log_index.as_u64(),
+                kind,
+            });
+        }
+        chunk_start = chunk_end + 1;
+    }
+    info!(events = events.len(), "Fetched pool events");
+    Ok(events)
+}
+
+/// What the strategy is run with, and what an action is assumed to cost
+#[derive(Clone, Debug)]
+pub struct BacktestParams {
+    pub ratio_range_allowed: (Decimal, Decimal),
+    pub ratio_range_targets: (Decimal, Decimal),
+    pub fee_rate: Decimal,
+    pub stable_coin_decimals: u64,
+    pub system_coin_decimals: u64,
+    pub stable_coin_is_token_0: bool, // Pairs order their tokens by address
+    pub slippage_bps: u32,
+    pub max_trade: Option<Decimal>, // Most system coin an action may mint or burn
+    pub keeper_fee_bps: u32,        // Paid to the keeper on the stable coin side of each action
+    pub gas_per_action: u64,
+    pub gas_price_gwei: Decimal,
+}
+
+impl BacktestParams {
+    pub fn from_config(config: &StrategyConfig) -> Self {
+        let token_pair = &config.token_pair;
+        BacktestParams {
+            ratio_range_allowed: config.ratio_range_allowed,
+            ratio_range_targets: config.ratio_range_targets,
+            fee_rate: config.uniswap_fee_rate,
+            stable_coin_decimals: token_pair.token_0.decimals,
+            system_coin_decimals: token_pair.token_1.decimals,
+            stable_coin_is_token_0: token_pair.token_0.address < token_pair.token_1.address,
+            slippage_bps: token_pair.slippage_bps,
+            max_trade: None,
+            keeper_fee_bps: 0,
+            gas_per_action: 400_000,
+            gas_price_gwei: Decimal::from(20),
+        }
+    }
+}
+
+/// Reserves of the pool as the backtest sees it, in token amounts
+#[derive(Clone, Copy, Debug)]
+struct SimulatedPool {
+    stable_coin: Decimal,
+    system_coin: Decimal,
+}
+
+impl SimulatedPool {
+    // Stable coin per system coin, the same price the keeper quotes
+    fn price(&self) -> Decimal {
+        self.stable_coin / self.system_coin
+    }
+
+    fn sell_stable_coin(&mut self, amount_in: Decimal, fee_rate: Decimal) -> Decimal {
+        let hop = Hop {
+            reserve_in: self.stable_coin,
+            reserve_out: self.system_coin,
+        };
+        let amount_out = get_amount_out(amount_in, &hop, fee_rate);
+        self.stable_coin += amount_in;
+        self.system_coin -= amount_out;
+        amount_out
+    }
+
+    fn sell_system_coin(&mut self, amount_in: Decimal, fee_rate: Decimal) -> Decimal {
+        let hop = Hop {
+            reserve_in: self.system_coin,
+            reserve_out: self.stable_coin,
+        };
+        let amount_out = get_amount_out(amount_in, &hop, fee_rate);
+        self.system_coin += amount_in;
+        self.stable_coin -= amount_out;
+        amount_out
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct PegStats {
+    blocks: usize,
+    blocks_off_peg: usize,
+    total_deviation: Decimal,
+    max_deviation: Decimal,
+}
+
+impl PegStats {
+    fn record(&mut self, price: Decimal, allowed_range: (Decimal, Decimal)) {
+        let deviation = (price - Decimal::ONE).abs();
+        self.blocks += 1;
+        self.total_deviation += deviation;
+        self.max_deviation = self.max_deviation.max(deviation);
+        if !decimal_is_within_allowed_range(price, allowed_range) {
+            self.blocks_off_peg += 1;
+        }
+    }
+
+    fn mean_deviation(&self) -> Decimal {
+        if self.blocks == 0 {
+            Decimal::ZERO
+        } else {
+            self.total_deviation / Decimal::from(self.blocks)
+        }
+    }
+}
+
+/// How the strategy fared over the replayed history, next to what actually happened
+#[derive(Clone, Debug, Default)]
+pub struct BacktestReport {
+    pub events: usize,
+    pub first_block: u64,
+    pub last_block: u64,
+    simulated: PegStats,
+    historical: PegStats,
+    pub expansions: usize,
+    pub contractions: usize,
+    pub reverted: usize, // Actions the pool had moved past the slippage bound of by the time they landed
+    pub system_coin_minted: Decimal,
+    pub system_coin_burned: Decimal,
+    pub module_debt: Decimal,      // Minted less burned, in system coin
+    pub peak_module_debt: Decimal, // The most the module had outstanding at once, in either direction
+    pub module_profit: Decimal, // Stable coin gained selling above peg and buying below, valuing system coin at par
+    pub keeper_fees: Decimal,   // In the stable coin
+    pub gas_cost_eth: Decimal,
+}
+
+impl BacktestReport {
+    pub fn simulated_mean_deviation(&self) -> Decimal {
+        self.simulated.mean_deviation()
+    }
+
+    pub fn simulated_max_deviation(&self) -> Decimal {
+        self.simulated.max_deviation
+    }
+
+    pub fn simulated_blocks_off_peg(&self) -> usize {
+        self.simulated.blocks_off_peg
+    }
+
+    pub fn historical_mean_deviation(&self) -> Decimal {
+        self.historical.mean_deviation()
+    }
+}
+
+#[cfg(test)]
+impl BacktestReport {
+    /**
+     * Records the keeper's run as having held the pair at `prices`, one block each.
+     */
+    pub(crate) fn with_simulated_prices(
+        mut self,
+        prices: &[Decimal],
+        allowed_range: (Decimal, Decimal),
+    ) -> Self {
+        for price in prices {
+            self.simulated.record(*price, allowed_range);
+        }
+        self
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |deviation: Decimal| (deviation * Decimal::ONE_HUNDRED).round_dp(4);
+        writeln!(
+            f,
+            "Backtest of {} events over blocks {}..={}",
+            self.events, self.first_block, self.last_block
+        )?;
+        writeln!(
+            f,
+            "  peg deviation     keeper mean={}% max={}%, historical mean={}% max={}%",
+            percent(self.simulated.mean_deviation()),
+            percent(self.simulated.max_deviation),
+            percent(self.historical.mean_deviation()),
+            percent(self.historical.max_deviation)
+        )?;
+        writeln!(
+            f,
+            "  blocks off peg    keeper {}/{}, historical {}/{}",
+            self.simulated.blocks_off_peg,
+            self.simulated.blocks,
+            self.historical.blocks_off_peg,
+            self.historical.blocks
+        )?;
+        writeln!(
+            f,
+            "  expansions        {}, minted {}",
+            self.expansions,
+            self.system_coin_minted.round_dp(6)
+        )?;
+        writeln!(
+            f,
+            "  contractions      {}, burned {}",
+            self.contractions,
+            self.system_coin_burned.round_dp(6)
+        )?;
+        writeln!(f, "  reverted          {}", self.reverted)?;
+        writeln!(
+            f,
+            "  module debt       {} (peak {}), profit {}",
+            self.module_debt.round_dp(6),
+            self.peak_module_debt.round_dp(6),
+            self.module_profit.round_dp(6)
+        )?;
+        write!(
+            f,
+            "  keeper fees       {} stable coin, gas {} ETH",
+            self.keeper_fees.round_dp(6),
+            self.gas_cost_eth.round_dp(6)
+        )
+    }
+}
+
+/// An action sent at the end of one block, landing in the next
+#[derive(Clone, Copy, Debug)]
+struct PendingAction {
+    expansion: bool,
+    amount_in: Decimal,
+    amount_out_min: Decimal,
+}
+
+struct Backtest<'a> {
+    params: &'a BacktestParams,
+    pool: Option<SimulatedPool>,
+    history: Option<SimulatedPool>, // The reserves as last synced on chain
+    pending: Option<PendingAction>,
+    report: BacktestReport,
+}
+
+impl Backtest<'_> {
+    // Orients a pair's token0/token1 amounts as stable coin and system coin
+    fn oriented(&self, amount_0: U256, amount_1: U256) -> (Decimal, Decimal) {
+        let (stable_coin, system_coin) = if self.params.stable_coin_is_token_0 {
+            (amount_0, amount_1)
+        } else {
+            (amount_1, amount_0)
+        };
+        (
+            u256_to_decimal(stable_coin, self.params.stable_coin_decimals),
+            u256_to_decimal(system_coin, self.params.system_coin_decimals),
+        )
+    }
+
+    /**
+     * Syncs that come with a swap only move the historical price, the swap itself is replayed.  Any other sync is a
+     * liquidity change, applied to the simulated pool in proportion.
+     */
+    fn apply_sync(&mut self, reserve0: U256, reserve1: U256, is_swap: bool) {
+        let (stable_coin, system_coin) = self.oriented(reserve0, reserve1);
+        let synced = SimulatedPool {
+            stable_coin,
+            system_coin,
+        };
+        match (&mut self.pool, &self.history) {
+            (None, _) => self.pool = Some(synced),
+            (Some(pool), Some(history)) if !is_swap => {
+                pool.stable_coin *= synced.stable_coin / history.stable_coin;
+                pool.system_coin *= synced.system_coin / history.system_coin;
+            }
+            _ => {}
+        }
+        self.history = Some(synced);
+    }
+
+    fn apply_swap(&mut self, amount0_in: U256, amount1_in: U256) {
+        let fee_rate = self.params.fee_rate;
+        let (stable_coin_in, system_coin_in) = self.oriented(amount0_in, amount1_in);
+        if let Some(pool) = &mut self.pool {
+            if !stable_coin_in.is_zero() {
+                pool.sell_stable_coin(stable_coin_in, fee_rate);
+            }
+            if !system_coin_in.is_zero() {
+                pool.sell_system_coin(system_coin_in, fee_rate);
+            }
+        }
+    }
+
+    /**
+     * The action sent last block lands after this block's swaps, reverting if they moved the pool past its minimum.
+     */
+    fn land_pending(&mut self) {
+        let params = self.params;
+        let (Some(pool), Some(action)) = (&mut self.pool, self.pending.take()) else {
+            return;
+        };
+        let report = &mut self.report;
+        report.gas_cost_eth += Decimal::from(params.gas_per_action) * params.gas_price_gwei
+            / Decimal::from(1_000_000_000u64);
+
+        let (reserve_in, reserve_out) = if action.expansion {
+            (pool.system_coin, pool.stable_coin)
+        } else {
+            (pool.stable_coin, pool.system_coin)
+        };
+        let hop = Hop {
+            reserve_in,
+            reserve_out,
+        };
+        if get_amount_out(action.amount_in, &hop, params.fee_rate) < action.amount_out_min {
+            debug!(?action, "Backtest action reverted");
+            report.reverted += 1;
+            return;
+        }
+
+        let stable_coin_traded = if action.expansion {
+            // Mint system coin and sell it for the stable coin
+            let amount_out = pool.sell_system_coin(action.amount_in, params.fee_rate);
+            report.expansions += 1;
+            report.system_coin_minted += action.amount_in;
+            report.module_debt += action.amount_in;
+            report.module_profit += amount_out - action.amount_in;
+            amount_out
+        } else {
+            // Sell the stable coin for system coin and burn it
+            let amount_out = pool.sell_stable_coin(action.amount_in, params.fee_rate);
+            report.contractions += 1;
+            report.system_coin_burned += amount_out;
+            report.module_debt -= amount_out;
+            report.module_profit += amount_out - action.amount_in;
+            action.amount_in
+        };
+        report.peak_module_debt = report.peak_module_debt.max(report.module_debt.abs());
+        report.keeper_fees += stable_coin_traded * Decimal::from(params.keeper_fee_bps)
+            / Decimal::from(BPS_DENOMINATOR);
+    }
+
+    /**
+     * What the keeper would send at the end of a block, sized the same way as on a direct Uniswap V2 pair.
+     */
+    fn plan(&mut self) {
+        let params = self.params;
+        let Some(pool) = &self.pool else {
+            return;
+        };
+        let price = pool.price();
+        if decimal_is_within_allowed_range(price, params.ratio_range_allowed) {
+            return;
+        }
+        let expansion = price > Decimal::ONE;
+        let goal_ratio = if expansion {
+            params.ratio_range_targets.1
+        } else {
+            params.ratio_range_targets.0
+        };
+        let quantity_to_buy =
+            direct_quantity_to_buy(pool.stable_coin, pool.system_coin, goal_ratio);
+        let hop = if expansion {
+            Hop {
+                reserve_in: pool.system_coin,
+                reserve_out: pool.stable_coin,
+            }
+        } else {
+            Hop {
+                reserve_in: pool.stable_coin,
+                reserve_out: pool.system_coin,
+            }
+        };
+        let mut amount_in = get_amount_in(quantity_to_buy, &hop, params.fee_rate);
+
+        // Caps are on the system coin minted or burned
+        if let Some(max_trade) = params.max_trade {
+            if expansion {
+                amount_in = amount_in.min(max_trade);
+            } else if quantity_to_buy > max_trade {
+                amount_in = get_amount_in(max_trade, &hop, params.fee_rate);
+            }
+        }
+        let amount_out = get_amount_out(amount_in, &hop, params.fee_rate);
+        debug!(%price, %amount_in, %amount_out, "Backtest action");
+        self.pending = Some(PendingAction {
+            expansion,
+            amount_in,
+            amount_out_min: apply_slippage_bps(amount_out, params.slippage_bps),
+        });
+    }
+
+    /**
+     * Closes a block: last block's action lands, the peg is measured, and the keeper decides on its next action.
+     */
+    fn end_block(&mut self) {
+        let (Some(_), Some(history)) = (&self.pool, &self.history) else {
+            return;
+        };
+        let historical_price = history.price();
+        self.land_pending();
+        let allowed_range = self.params.ratio_range_allowed;
+        self.report
+            .historical
+            .record(historical_price, allowed_range);
+        if let Some(pool) = &self.pool {
+            self.report.simulated.record(pool.price(), allowed_range);
+        }
+        self.plan();
+    }
+}
+
+/**
+ * Replays the pool's history with the keeper deciding at the end of every block.  Its actions land in the next block
+ * after that block's swaps, which are replayed against the simulated pool so their price impact lands on the pool as
+ * the keeper left it.
+ */
+pub fn run_backtest(events: &[PoolEvent], params: &BacktestParams) -> BacktestReport {
+    let mut backtest = Backtest {
+        params,
+        pool: None,
+        history: None,
+        pending: None,
+        report: BacktestReport {
+            events: events.len(),
+            first_block: events.first().map_or(0, |event| event.block_number),
+            last_block: events.last().map_or(0, |event| event.block_number),
+            ..Default::default()
+        },
+    };
+
+    for (index, event) in events.iter().enumerate() {
+        let next = events.get(index + 1);
+        match &event.kind {
+            PoolEventKind::Sync { reserve0, reserve1 } => {
+                // Pairs emit Sync right before the Swap that caused it
+                let is_swap = next.is_some_and(|next| {
+                    next.block_number == event.block_number
+                        && matches!(next.kind, PoolEventKind::Swap { .. })
+                });
+                backtest.apply_sync(*reserve0, *reserve1, is_swap);
+            }
+            PoolEventKind::Swap {
+                amount0_in,
+                amount1_in,
+                ..
+            } => backtest.apply_swap(*amount0_in, *amount1_in),
+        }
+        if next.is_none_or(|next| next.block_number != event.block_number) {
+            backtest.end_block();
+        }
+    }
+    // The last action still lands, in a block without other swaps
+    backtest.land_pending();
+    backtest.report
+}
+
+#[derive(Args)]
+pub struct BacktestArgs {
+    /// JSONL dump of the pair's events to replay
+    #[arg(
+        long,
+        conflicts_with = "from_block",
+        required_unless_present = "from_block"
+    )]
+    events: Option<PathBuf>,
+    /// First block to fetch the pair's events from
+    #[arg(long, requires = "to_block")]
+    from_block: Option<u64>,
+    /// Last block to fetch the pair's events from
+    #[arg(long, requires = "from_block")]
+    to_block: Option<u64>,
+    /// Save the fetched events as JSONL, to replay them later with --events
+    #[arg(long, requires = "from_block")]
+    save_events: Option<PathBuf>,
+    #[command(flatten)]
+    costs: CostArgs,
+}
+
+/// What the keeper is paid and pays for each action, shared by backtest and sweep
+#[derive(Args)]
+pub struct CostArgs {
+    /// Fee the stability module pays the keeper on each action, in basis points
+    #[arg(long, default_value_t = 0)]
+    keeper_fee_bps: u32,
+    /// Gas an action is assumed to use
+    #[arg(long, default_value_t = 400_000)]
+    gas_per_action: u64,
+    /// Gas price actions are assumed to pay
+    #[arg(long, default_value = "20")]
+    gas_price_gwei: Decimal,
+}
+
+impl CostArgs {
+    pub fn backtest_params(&self, config: &StrategyConfig) -> BacktestParams {
+        BacktestParams {
+            keeper_fee_bps: self.keeper_fee_bps,
+            gas_per_action: self.gas_per_action,
+            gas_price_gwei: self.gas_price_gwei,
+            ..BacktestParams::from_config(config)
+        }
+    }
+}
+
+/**
+ * Backtests the configured pair against a saved history, or one fetched for a block range.  Only fetching reaches an
+ * endpoint, replaying a saved history needs nothing but the pair configured.
+ */
+pub async fn command(args: BacktestArgs) -> Result<()> {
+    let config = config::generate_strategy_config();
+    let token_pair = &config.token_pair;
+    let events = match (&args.events, args.from_block, args.to_block) {
+        (Some(path), _, _) => load_events(path)?,
+        (None, Some(from_block), Some(to_block)) => {
+            let provider = Arc::new(Provider::new(FailoverClient::new(
+                &config::generate_rpc_urls(),
+            )?));
+            let uniswap_factory =
+                UniswapV2Factory::new(config::generate_uniswap_factory_address(), provider.clone());
+            let pair_address = uniswap_factory
+                .get_pair(token_pair.token_0.address, token_pair.token_1.address)
+                .call()
+                .await?;
+            let events = fetch_events(provider, pair_address, from_block, to_block).await?;
+            if let Some(path) = &args.save_events {
+                save_events(path, &events)?;
+            }
+            events
+        }
+        _ => bail!("Either --events or a block range is required"),
+    };
+
+    let params = args.costs.backtest_params(&config);
+    println!("{}", run_backtest(&events, &params));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::parse_units;
+
+    fn units(amount: &str) -> U256 {
+        parse_units(amount, 18).unwrap().into()
+    }
+
+    fn params() -> BacktestParams {
+        BacktestParams {
+            ratio_range_allowed: (
+                Decimal::from_str_exact("0.996").unwrap(),
+                Decimal::from_str_exact("1.002").unwrap(),
+            ),
+            ratio_range_targets: (
+                Decimal::from_str_exact("0.997").unwrap(),
+                Decimal::from_str_exact("1.001").unwrap(),
+            ),
+            fee_rate: Decimal::from_str_exact("0.003").unwrap(),
+            stable_coin_decimals: 18,
+            system_coin_decimals: 18,
+            stable_coin_is_token_0: true,
+            slippage_bps: 50,
+            max_trade: None,
+            keeper_fee_bps: 10,
+            gas_per_action: 400_000,
+            gas_price_gwei: Decimal::from(20),
+        }
+    }
+
+    fn sync(block_number: u64, log_index: u64, stable_coin: &str, system_coin: &str) -> PoolEvent {
+        PoolEvent {
+            block_number,
+            log_index,
+            kind: PoolEventKind::Sync {
+                reserve0: units(stable_coin),
+                reserve1: units(system_coin),
+            },
+        }
+    }
+
+    fn swap(
+        block_number: u64,
+        log_index: u64,
+        stable_coin_in: &str,
+        system_coin_in: &str,
+    ) -> PoolEvent {
+        PoolEvent {
+            block_number,
+            log_index,
+            kind: PoolEventKind::Swap {
+                amount0_in: units(stable_coin_in),
+                amount1_in: units(system_coin_in),
+                amount0_out: U256::zero(),
+                amount1_out: U256::zero(),
+            },
+        }
+    }
+
+    // 20k of stable coin buys up the system coin in block 2
+    fn buy_of_system_coin() -> Vec<PoolEvent> {
+        vec![
+            sync(1, 0, "1000000", "1000000"),
+            sync(2, 0, "1020000", "980467.47"),
+            swap(2, 1, "20000", "0"),
+        ]
+    }
+
+    #[test]
+    fn a_buy_of_system_coin_is_met_with_an_expansion() {
+        let mut events = buy_of_system_coin();
+        events.extend([sync(3, 0, "1020001", "980466.51"), swap(3, 1, "1", "0")]);
+        let report = run_backtest(&events, &params());
+
+        assert_eq!(report.expansions, 1);
+        assert_eq!(report.contractions, 0);
+        assert!(report.system_coin_minted > Decimal::ZERO);
+        assert_eq!(report.module_debt, report.system_coin_minted);
+        assert!(report.module_profit > Decimal::ZERO);
+        // Off peg for the block it was bought in, back on once the expansion lands
+        assert_eq!(report.simulated_blocks_off_peg(), 1);
+        assert!(report.historical_mean_deviation() > report.simulated_mean_deviation());
+        assert!(report.keeper_fees > Decimal::ZERO);
+        assert_eq!(
+            report.gas_cost_eth,
+            Decimal::from_str_exact("0.008").unwrap()
+        );
+    }
+
+    #[test]
+    fn actions_revert_when_the_pool_moves_past_the_slippage_bound() {
+        // Someone else sells system coin into the pool before the expansion lands
+        let mut events = buy_of_system_coin();
+        events.extend([sync(3, 0, "1010000", "990000"), swap(3, 1, "0", "10000")]);
+        let report = run_backtest(&events, &params());
+        assert_eq!(report.reverted, 1);
+        // Still off peg after the revert, so it's tried again from the new state
+        assert_eq!(report.expansions, 1);
+
+        let report = run_backtest(
+            &events,
+            &BacktestParams {
+                slippage_bps: 5_000,
+                ..params()
+            },
+        );
+        assert_eq!(report.reverted, 0);
+    }
+
+    #[test]
+    fn trades_are_capped() {
+        let max_trade = Decimal::from(1_000);
+        let report = run_backtest(
+            &buy_of_system_coin(),
+            &BacktestParams {
+                max_trade: Some(max_trade),
+                ..params()
+            },
+        );
+        assert_eq!(report.system_coin_minted, max_trade);
+    }
+
+    #[test]
+    fn liquidity_changes_keep_the_simulated_price() {
+        let events = vec![
+            sync(1, 0, "1000000", "1000000"),
+            // Liquidity doubled, no swap in the block
+            sync(2, 0, "2000000", "2000000"),
+        ];
+        let report = run_backtest(&events, &params());
+        assert_eq!(report.expansions + report.contractions, 0);
+        assert_eq!(report.simulated_mean_deviation(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn events_round_trip_through_jsonl() {
+        let path = std::env::temp_dir().join(format!("azos-backtest-{}.jsonl", std::process::id()));
+        let events = vec![sync(1, 0, "1000000", "1000000"), swap(1, 1, "5", "0")];
+        save_events(&path, &events).unwrap();
+        assert_eq!(load_events(&path).unwrap(), events);
+        std::fs::remove_file(path).unwrap();
+    }
+}
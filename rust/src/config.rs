@@ -1,25 +1,205 @@
 use super::types::token::{Token, TokenPair};
+use super::types::venue::{PairVenue, Venue, VenueSelection};
+use super::utils::decimal::BPS_DENOMINATOR;
 use ethers::abi::Address;
 use rust_decimal::Decimal;
 use std::env;
 
+/// How long the keeper waits between ticks, and the indexer between polls
+pub const DELAY_BETWEEN_CHECKS_MS: i32 = 3_000;
+
 pub struct Config {
-    pub rpc_url: String,
+    pub rpc_urls: Vec<String>, // Tried in order of health, failing over when one can't be reached
+    pub rpc_quorum: Option<usize>, // How many endpoints must agree on reserves and module debt
     pub delay_between_checks_ms: i32,
     pub token_pairs: Vec<TokenPair>,
-    pub keeper_wallet_private_key: String,
+    pub keeper_signer: SignerConfig,
     pub uniswap_router_address: Address,
     pub uniswap_factory_address: Address,
     pub uniswap_fee_rate: Decimal,
     pub stability_module_address: Address,
     pub ratio_range_allowed: (Decimal, Decimal), // Range where swapping is considered unprofitable
     pub ratio_range_targets: (Decimal, Decimal), // Range where we aim to move the price to
-    pub adapter_name: String,
-    pub adapter_uniswap_v2_address: Address,
+    pub adapters: Vec<AdapterConfig>,
     pub tx_confirmations_required: usize,
+    pub uniswap_v3: Option<UniswapV3Config>,
+    pub routing_tokens: Vec<Address>, // Intermediate tokens Uniswap V2 swaps may route through
+    pub route_discovery_max_pairs: usize, // How many factory pairs to scan for routes, 0 to disable
+    pub venue_selection: VenueSelection,
+    pub swap_deadline_seconds: u64, // How long after the latest block a swap stays valid
+    pub max_clock_skew_seconds: u64, // How far the local clock may drift from chain time before health degrades
+    pub authorization_required: bool, // Whether the module only takes calls from accounts it has authorized
+    pub bundle_relay: Option<BundleRelayConfig>,
+    pub alerts: Option<AlertConfig>,
 }
 
-pub fn generate_config() -> Config {
+pub struct AdapterConfig {
+    pub name: String,
+    pub address: Address,
+    pub kind: AdapterKind,
+}
+
+pub enum AdapterKind {
+    UniswapV2 { router_address: Address },
+    UniswapV3 { router_address: Address },
+    Curve,
+}
+
+pub struct UniswapV3Config {
+    pub factory_address: Address,
+    pub router_address: Address,
+    pub quoter_address: Address, // QuoterV2, re-quotes swaps right before they're sent
+    pub adapter_name: String,
+    pub adapter_address: Address,
+    pub max_tick_boundaries: usize, // Tick boundaries inspected per evaluation, each one costs an RPC call
+}
+
+pub struct CurveConfig {
+    pub pool_address: Address,
+    pub adapter_name: String,
+    pub adapter_address: Address,
+}
+
+/// Where the keeper's key lives, only one source is used
+pub enum SignerConfig {
+    PrivateKey(String),
+    Keystore { path: String, password_file: String },
+    Remote { url: String, address: Address },
+}
+
+pub fn generate_signer_config() -> SignerConfig {
+    if let Ok(path) = env::var("KEEPER_KEYSTORE_PATH") {
+        SignerConfig::Keystore {
+            path,
+            password_file: env::var("KEEPER_KEYSTORE_PASSWORD_FILE")
+                .expect("KEEPER_KEYSTORE_PASSWORD_FILE environment variable not set"),
+        }
+    } else if let Ok(url) = env::var("KEEPER_REMOTE_SIGNER_URL") {
+        SignerConfig::Remote {
+            url,
+            address: env::var("KEEPER_REMOTE_SIGNER_ADDRESS")
+                .expect("KEEPER_REMOTE_SIGNER_ADDRESS environment variable not set")
+                .parse()
+                .expect("KEEPER_REMOTE_SIGNER_ADDRESS not a valid address"),
+        }
+    } else {
+        SignerConfig::PrivateKey(
+            env::var("KEEPER_WALLET_PRIVATE_KEY")
+                .expect("KEEPER_WALLET_PRIVATE_KEY environment variable not set"),
+        )
+    }
+}
+
+pub struct BundleRelayConfig {
+    pub url: String,
+    pub max_blocks: u64, // Blocks to target privately before falling back to the public mempool
+}
+
+fn generate_bundle_relay_config() -> Option<BundleRelayConfig> {
+    // Private submission is only enabled when a relay is configured
+    let url = env::var("BUNDLE_RELAY_URL").ok()?;
+
+    Some(BundleRelayConfig {
+        url,
+        max_blocks: env::var("BUNDLE_RELAY_MAX_BLOCKS")
+            .unwrap_or(String::from("3"))
+            .parse()
+            .expect("BUNDLE_RELAY_MAX_BLOCKS is not a valid number of blocks"),
+    })
+}
+
+pub struct AlertConfig {
+    pub webhook_urls: Vec<String>, // Slack incoming webhooks or Discord webhooks
+    pub wallet_balance_floor: Option<Decimal>, // In ETH, alerting when the keeper's balance falls below it
+    pub out_of_band_blocks: u64, // How many blocks a pair may stay out of range before alerting
+    pub dedup_seconds: u64,      // How long the same alert is held back after being sent
+    pub max_per_minute: usize,
+}
+
+fn generate_alert_config() -> Option<AlertConfig> {
+    // Alerting is only enabled when a webhook is configured
+    let webhook_urls: Vec<String> = env::var("ALERT_WEBHOOK_URLS")
+        .ok()?
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+
+    Some(AlertConfig {
+        webhook_urls,
+        wallet_balance_floor: env::var("ALERT_WALLET_BALANCE_FLOOR").ok().map(|floor| {
+            Decimal::from_str_exact(&floor).expect("ALERT_WALLET_BALANCE_FLOOR is not a number")
+        }),
+        out_of_band_blocks: env::var("ALERT_OUT_OF_BAND_BLOCKS")
+            .unwrap_or(String::from("10"))
+            .parse()
+            .expect("ALERT_OUT_OF_BAND_BLOCKS is not a valid number of blocks"),
+        dedup_seconds: env::var("ALERT_DEDUP_SECONDS")
+            .unwrap_or(String::from("600"))
+            .parse()
+            .expect("ALERT_DEDUP_SECONDS is not a valid number of seconds"),
+        max_per_minute: env::var("ALERT_MAX_PER_MINUTE")
+            .unwrap_or(String::from("10"))
+            .parse()
+            .expect("ALERT_MAX_PER_MINUTE is not a number"),
+    })
+}
+
+fn generate_curve_config() -> Option<CurveConfig> {
+    // Curve is only enabled when a pool is configured
+    let pool_address = env::var("CURVE_POOL_ADDRESS")
+        .ok()?
+        .parse()
+        .expect("CURVE_POOL_ADDRESS not a valid address");
+
+    Some(CurveConfig {
+        pool_address,
+        adapter_name: env::var("ADAPTER_CURVE_NAME").unwrap_or(String::from("USDC_CURVE")),
+        adapter_address: env::var("ADAPTER_CURVE_ADDRESS")
+            .expect("ADAPTER_CURVE_ADDRESS environment variable not set")
+            .parse()
+            .expect("ADAPTER_CURVE_ADDRESS is not valid"),
+    })
+}
+
+fn generate_uniswap_v3_config() -> Option<UniswapV3Config> {
+    // Uniswap V3 is only enabled when a factory is configured
+    let factory_address = env::var("UNISWAP_V3_FACTORY_ADDRESS")
+        .ok()?
+        .parse()
+        .expect("UNISWAP_V3_FACTORY_ADDRESS not a valid address");
+
+    Some(UniswapV3Config {
+        factory_address,
+        router_address: env::var("UNISWAP_V3_ROUTER_ADDRESS")
+            .expect("UNISWAP_V3_ROUTER_ADDRESS environment variable not set")
+            .parse()
+            .expect("UNISWAP_V3_ROUTER_ADDRESS not a valid address"),
+        quoter_address: env::var("UNISWAP_V3_QUOTER_ADDRESS")
+            .expect("UNISWAP_V3_QUOTER_ADDRESS environment variable not set")
+            .parse()
+            .expect("UNISWAP_V3_QUOTER_ADDRESS not a valid address"),
+        adapter_name: env::var("ADAPTER_UNISWAP_V3_NAME").unwrap_or(String::from("USDC_V3")),
+        adapter_address: env::var("ADAPTER_UNISWAP_V3_ADDRESS")
+            .expect("ADAPTER_UNISWAP_V3_ADDRESS environment variable not set")
+            .parse()
+            .expect("ADAPTER_UNISWAP_V3_ADDRESS is not valid"),
+        max_tick_boundaries: env::var("UNISWAP_V3_MAX_TICK_BOUNDARIES")
+            .unwrap_or(String::from("100"))
+            .parse()
+            .expect("UNISWAP_V3_MAX_TICK_BOUNDARIES is not a valid number"),
+    })
+}
+
+/// The pair and the ranges the keeper holds it in, all that replaying history offline needs
+pub struct StrategyConfig {
+    pub token_pair: TokenPair, // Without venues, they're only known along with their adapters
+    pub uniswap_fee_rate: Decimal,
+    pub ratio_range_allowed: (Decimal, Decimal),
+    pub ratio_range_targets: (Decimal, Decimal),
+}
+
+pub fn generate_strategy_config() -> StrategyConfig {
     let usdc = Token {
         symbol: String::from("USDC"),
         address: env::var("USDC_ADDRESS")
@@ -38,53 +218,210 @@ pub fn generate_config() -> Config {
         decimals: 18,
     };
 
-    let token_pairs = vec![TokenPair {
-        symbol: String::from("USDC/ZAI"),
-        token_0: usdc,
-        token_1: zai,
-    }];
+    let slippage_bps: u32 = env::var("USDC_ZAI_SLIPPAGE_BPS")
+        .unwrap_or(String::from("50"))
+        .parse()
+        .expect("USDC_ZAI_SLIPPAGE_BPS is not a valid number of basis points");
+    if slippage_bps > BPS_DENOMINATOR {
+        panic!("USDC_ZAI_SLIPPAGE_BPS can't be more than {BPS_DENOMINATOR}");
+    }
 
     let uniswap_fee_rate_string =
         env::var("UNISWAP_FEE_RATE").expect("UNISWAP_FEE_RATE environment variable not set");
 
-    let stability_module_address: Address = env::var("STABILITY_MODULE_ADDRESS")
+    StrategyConfig {
+        token_pair: TokenPair {
+            symbol: String::from("USDC/ZAI"),
+            token_0: usdc,
+            token_1: zai,
+            venues: vec![],
+            slippage_bps,
+        },
+        uniswap_fee_rate: Decimal::from_str_exact(uniswap_fee_rate_string.as_str()).unwrap(),
+        ratio_range_allowed: (
+            Decimal::from_str_exact("0.996").unwrap(),
+            Decimal::from_str_exact("1.002").unwrap(),
+        ),
+        ratio_range_targets: (
+            Decimal::from_str_exact("0.997").unwrap(),
+            Decimal::from_str_exact("1.001").unwrap(),
+        ),
+    }
+}
+
+pub fn generate_rpc_urls() -> Vec<String> {
+    // RPC_URLS lists every endpoint, RPC_URL is kept for single endpoint setups
+    env::var("RPC_URLS")
+        .or_else(|_| env::var("RPC_URL"))
+        .expect("RPC_URL environment variable not set")
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect()
+}
+
+pub fn generate_stability_module_address() -> Address {
+    env::var("STABILITY_MODULE_ADDRESS")
         .expect("STABILITY_MODULE_ADDRESS environment variable not set")
         .parse()
-        .expect("STABILITY_MODULE_ADDRESS is not valid");
+        .expect("STABILITY_MODULE_ADDRESS is not valid")
+}
+
+pub fn generate_uniswap_factory_address() -> Address {
+    env::var("UNISWAP_FACTORY_ADDRESS")
+        .expect("UNISWAP_FACTORY_ADDRESS environment variable not set")
+        .parse()
+        .expect("UNISWAP_FACTORY_ADDRESS not a valid address")
+}
+
+fn generate_rpc_quorum(endpoints: usize) -> Option<usize> {
+    let quorum: usize = env::var("RPC_QUORUM")
+        .ok()?
+        .parse()
+        .expect("RPC_QUORUM is not a valid number of endpoints");
+    if quorum == 0 {
+        panic!("RPC_QUORUM must be at least 1");
+    }
+    if quorum > endpoints {
+        panic!("RPC_QUORUM of {quorum} needs at least as many RPC endpoints, only {endpoints} configured");
+    }
+    Some(quorum)
+}
+
+pub fn generate_config() -> Config {
+    let StrategyConfig {
+        mut token_pair,
+        uniswap_fee_rate,
+        ratio_range_allowed,
+        ratio_range_targets,
+    } = generate_strategy_config();
+
+    let uniswap_v3 = generate_uniswap_v3_config();
+
+    let uniswap_router_address: Address = env::var("UNISWAP_ROUTER_ADDRESS")
+        .expect("UNISWAP_ROUTER_ADDRESS environment variable not set")
+        .parse()
+        .expect("UNISWAP_ROUTER_ADDRESS not a valid address");
 
     let adapter_uniswap_v2_address: Address = env::var("ADAPTER_UNISWAP_V2_ADDRESS")
         .expect("ADAPTER_UNISWAP_V2_ADDRESS environment variable not set")
         .parse()
         .expect("ADAPTER_UNISWAP_V2_ADDRESS is not valid");
+    let adapter_uniswap_v2_name =
+        env::var("ADAPTER_UNISWAP_V2_NAME").unwrap_or(String::from("USDC"));
+
+    let mut adapters = vec![AdapterConfig {
+        name: adapter_uniswap_v2_name.clone(),
+        address: adapter_uniswap_v2_address,
+        kind: AdapterKind::UniswapV2 {
+            router_address: uniswap_router_address,
+        },
+    }];
+    let mut venues = vec![PairVenue {
+        venue: Venue::UniswapV2,
+        adapter_name: adapter_uniswap_v2_name,
+    }];
+    if let Some(uniswap_v3) = &uniswap_v3 {
+        adapters.push(AdapterConfig {
+            name: uniswap_v3.adapter_name.clone(),
+            address: uniswap_v3.adapter_address,
+            kind: AdapterKind::UniswapV3 {
+                router_address: uniswap_v3.router_address,
+            },
+        });
+        let fee: u32 = env::var("UNISWAP_V3_POOL_FEE")
+            .unwrap_or(String::from("500"))
+            .parse()
+            .expect("UNISWAP_V3_POOL_FEE is not a valid fee tier");
+        venues.push(PairVenue {
+            venue: Venue::UniswapV3 { fee },
+            adapter_name: uniswap_v3.adapter_name.clone(),
+        });
+    }
+    if let Some(curve) = generate_curve_config() {
+        adapters.push(AdapterConfig {
+            name: curve.adapter_name.clone(),
+            address: curve.adapter_address,
+            kind: AdapterKind::Curve,
+        });
+        let usdc_index: usize = env::var("CURVE_USDC_INDEX")
+            .unwrap_or(String::from("0"))
+            .parse()
+            .expect("CURVE_USDC_INDEX is not a valid coin index");
+        let (usdc, zai) = (token_pair.token_0.address, token_pair.token_1.address);
+        let coins = if usdc_index == 0 {
+            [usdc, zai]
+        } else {
+            [zai, usdc]
+        };
+        venues.push(PairVenue {
+            venue: Venue::CurveStableSwap {
+                pool: curve.pool_address,
+                coins,
+            },
+            adapter_name: curve.adapter_name,
+        });
+    }
+
+    token_pair.venues = venues;
+
+    let rpc_urls = generate_rpc_urls();
+
+    let venue_selection = match env::var("VENUE_SELECTION")
+        .unwrap_or(String::from("best"))
+        .as_str()
+    {
+        "best" => VenueSelection::Best,
+        "split" => VenueSelection::Split,
+        other => panic!("VENUE_SELECTION must be best or split, not {other}"),
+    };
+
+    let routing_tokens: Vec<Address> = env::var("ROUTING_TOKENS")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|address| !address.trim().is_empty())
+        .map(|address| {
+            address
+                .trim()
+                .parse()
+                .expect("ROUTING_TOKENS contains an invalid address")
+        })
+        .collect();
 
     Config {
-        rpc_url: env::var("RPC_URL").expect("RPC_URL environment variable not set"),
-        keeper_wallet_private_key: env::var("KEEPER_WALLET_PRIVATE_KEY")
-            .expect("KEEPER_WALLET_PRIVATE_KEY environment variable not set")
+        rpc_quorum: generate_rpc_quorum(rpc_urls.len()),
+        rpc_urls,
+        keeper_signer: generate_signer_config(),
+        uniswap_router_address,
+        uniswap_factory_address: generate_uniswap_factory_address(),
+        delay_between_checks_ms: DELAY_BETWEEN_CHECKS_MS,
+        uniswap_fee_rate,
+        token_pairs: vec![token_pair],
+        stability_module_address: generate_stability_module_address(),
+        adapters,
+        tx_confirmations_required: 2,
+        uniswap_v3,
+        routing_tokens,
+        venue_selection,
+        swap_deadline_seconds: env::var("SWAP_DEADLINE_SECONDS")
+            .unwrap_or(String::from("120"))
             .parse()
-            .expect("KEEPER_WALLET_PRIVATE_KEY not a valid address"),
-        uniswap_router_address: env::var("UNISWAP_ROUTER_ADDRESS")
-            .expect("UNISWAP_ROUTER_ADDRESS environment variable not set")
+            .expect("SWAP_DEADLINE_SECONDS is not a valid number of seconds"),
+        max_clock_skew_seconds: env::var("MAX_CLOCK_SKEW_SECONDS")
+            .unwrap_or(String::from("30"))
             .parse()
-            .expect("UNISWAP_ROUTER_ADDRESS not a valid address"),
-        uniswap_factory_address: env::var("UNISWAP_FACTORY_ADDRESS")
-            .expect("UNISWAP_FACTORY_ADDRESS environment variable not set")
+            .expect("MAX_CLOCK_SKEW_SECONDS is not a valid number of seconds"),
+        authorization_required: env::var("KEEPER_AUTHORIZATION_REQUIRED")
+            .unwrap_or(String::from("false"))
             .parse()
-            .expect("UNISWAP_FACTORY_ADDRESS not a valid address"),
-        delay_between_checks_ms: 3_000,
-        uniswap_fee_rate: Decimal::from_str_exact(uniswap_fee_rate_string.as_str()).unwrap(),
-        token_pairs,
-        stability_module_address,
-        adapter_name: String::from("USDC"),
-        adapter_uniswap_v2_address,
-        tx_confirmations_required: 2,
-        ratio_range_allowed: (
-            Decimal::from_str_exact("0.996").unwrap(),
-            Decimal::from_str_exact("1.002").unwrap(),
-        ),
-        ratio_range_targets: (
-            Decimal::from_str_exact("0.997").unwrap(),
-            Decimal::from_str_exact("1.001").unwrap(),
-        ),
+            .expect("KEEPER_AUTHORIZATION_REQUIRED is not true or false"),
+        bundle_relay: generate_bundle_relay_config(),
+        alerts: generate_alert_config(),
+        route_discovery_max_pairs: env::var("ROUTE_DISCOVERY_MAX_PAIRS")
+            .unwrap_or(String::from("0"))
+            .parse()
+            .expect("ROUTE_DISCOVERY_MAX_PAIRS is not a number"),
+        ratio_range_allowed,
+        ratio_range_targets,
     }
 }
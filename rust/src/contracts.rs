@@ -1,5 +1,15 @@
+// Bindings are generated by build.rs, keep the lints for generated code quiet
+#![allow(clippy::module_inception, clippy::useless_conversion)]
+
+pub mod azos_adapter_curve;
 pub mod azos_adapter_uniswap_v2;
+pub mod azos_adapter_uniswap_v3;
 pub mod azos_stability_module;
+pub mod curve_stable_swap;
+pub mod erc20;
 pub mod uniswap_v2_factory;
 pub mod uniswap_v2_pair;
 pub mod uniswap_v2_router02;
+pub mod uniswap_v3_factory;
+pub mod uniswap_v3_pool;
+pub mod uniswap_v3_quoter_v2;
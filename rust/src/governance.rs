@@ -0,0 +1,131 @@
+use crate::indexer::ModuleEvent;
+use crate::types::provider::StabilityModule;
+use anyhow::Result;
+use ethers::prelude::*;
+use ethers::utils::format_bytes32_string;
+use std::collections::HashMap;
+
+/// The keeper's view of the stability module's governance parameters, kept up to date from its events
+#[derive(Clone, Debug, Default)]
+pub struct ModuleView {
+    pub debt_ceiling: U256,
+    pub max_deposit: U256,
+    pub keeper_fee: Option<U256>, // The module has no getter, it's only known once a `KeeperFee` event is seen
+    pub treasury: Option<Address>, // Likewise, from `ChangeTreasury`
+    pub adapters: HashMap<String, Address>, // Where each configured adapter name is registered
+    pub wound_down: bool,
+}
+
+impl ModuleView {
+    /**
+     * Reads the parameters the module exposes, and the registrations of `adapter_names`.
+     */
+    pub async fn load<M: Middleware + 'static>(
+        stability_module: &StabilityModule<M>,
+        adapter_names: &[&str],
+    ) -> Result<Self> {
+        let mut adapters = HashMap::new();
+        for name in adapter_names {
+            let registered = stability_module
+                .get_adapter(format_bytes32_string(name)?)
+                .call()
+                .await?;
+            adapters.insert(name.to_string(), registered);
+        }
+        Ok(ModuleView {
+            debt_ceiling: stability_module.debt_ceiling().call().await?,
+            max_deposit: stability_module.max_deposit().call().await?,
+            adapters,
+            ..Default::default()
+        })
+    }
+
+    /**
+     * Applies a governance event, describing the change when it's one the view follows.
+     */
+    pub fn apply(&mut self, event: &ModuleEvent) -> Option<String> {
+        match event {
+            ModuleEvent::KeeperFee { new_fee } => {
+                self.keeper_fee = Some(*new_fee);
+                Some(format!("keeper fee is now {new_fee}"))
+            }
+            ModuleEvent::DebtCeilingChange { debt_ceiling } => {
+                self.debt_ceiling = *debt_ceiling;
+                Some(format!("debt ceiling is now {debt_ceiling}"))
+            }
+            ModuleEvent::MaxDeposit { max_deposit } => {
+                self.max_deposit = *max_deposit;
+                Some(format!("max deposit is now {max_deposit}"))
+            }
+            ModuleEvent::ChangeTreasury { new_treasury, .. } => {
+                self.treasury = Some(*new_treasury);
+                Some(format!("treasury is now {new_treasury:?}"))
+            }
+            // Only the names the keeper swaps through matter
+            ModuleEvent::AddAdapter {
+                adapter_name,
+                adapter,
+                ..
+            } if self.adapters.contains_key(adapter_name) => {
+                self.adapters.insert(adapter_name.clone(), *adapter);
+                Some(format!("adapter {adapter_name} is now {adapter:?}"))
+            }
+            ModuleEvent::WindDown { treasury, .. } => {
+                self.wound_down = true;
+                Some(format!("module wound down to treasury {treasury:?}"))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn governance_events_update_the_view() {
+        let mut view = ModuleView {
+            adapters: HashMap::from([(String::from("USDC"), Address::repeat_byte(0x30))]),
+            ..Default::default()
+        };
+
+        view.apply(&ModuleEvent::DebtCeilingChange {
+            debt_ceiling: U256::from(1_000),
+        });
+        view.apply(&ModuleEvent::KeeperFee {
+            new_fee: U256::from(5),
+        });
+        assert_eq!(view.debt_ceiling, U256::from(1_000));
+        assert_eq!(view.keeper_fee, Some(U256::from(5)));
+
+        // Adapters the keeper doesn't use are left out
+        let other = ModuleEvent::AddAdapter {
+            adapter_name: String::from("DAI"),
+            adapter: Address::repeat_byte(0x31),
+            authorized: Address::zero(),
+        };
+        assert!(view.apply(&other).is_none());
+        assert!(!view.adapters.contains_key("DAI"));
+        view.apply(&ModuleEvent::AddAdapter {
+            adapter_name: String::from("USDC"),
+            adapter: Address::repeat_byte(0x32),
+            authorized: Address::zero(),
+        });
+        assert_eq!(view.adapters["USDC"], Address::repeat_byte(0x32));
+
+        assert!(view
+            .apply(&ModuleEvent::Deposit {
+                amount: U256::one()
+            })
+            .is_none());
+        assert!(!view.wound_down);
+        view.apply(&ModuleEvent::WindDown {
+            treasury: Address::repeat_byte(0x50),
+            collateral_amount: U256::zero(),
+            coin_amount: U256::zero(),
+            authorized: Address::zero(),
+        });
+        assert!(view.wound_down);
+    }
+}
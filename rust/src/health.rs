@@ -0,0 +1,101 @@
+use std::fmt;
+use tracing::warn;
+
+/// Signals about the keeper's own state, as opposed to the pools it watches
+#[derive(Default)]
+pub struct Health {
+    clock_skew_seconds: i64,
+    max_clock_skew_seconds: u64,
+    module_wound_down: bool,
+    unauthorized: bool,
+}
+
+impl Health {
+    pub fn new(max_clock_skew_seconds: u64) -> Self {
+        Health {
+            max_clock_skew_seconds,
+            ..Default::default()
+        }
+    }
+
+    pub fn record_clock_skew(&mut self, clock_skew_seconds: i64) {
+        self.clock_skew_seconds = clock_skew_seconds;
+        if !self.clock_is_healthy() {
+            warn!(
+                clock_skew_seconds,
+                max_clock_skew_seconds = self.max_clock_skew_seconds,
+                "Local clock is further off chain time than allowed"
+            );
+        }
+    }
+
+    pub fn record_wind_down(&mut self) {
+        self.module_wound_down = true;
+    }
+
+    pub fn record_authorization(&mut self, authorized: bool) {
+        self.unauthorized = !authorized;
+    }
+
+    pub fn clock_is_healthy(&self) -> bool {
+        self.clock_skew_seconds.unsigned_abs() <= self.max_clock_skew_seconds
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.clock_is_healthy() && !self.module_wound_down && !self.unauthorized
+    }
+
+    /**
+     * The summary without its measurements, which only changes when the keeper's state does.
+     */
+    pub fn state(&self) -> &'static str {
+        if self.module_wound_down {
+            "stopped, module wound down"
+        } else if self.unauthorized {
+            "paused, unauthorized"
+        } else if self.is_healthy() {
+            "ok"
+        } else {
+            "degraded"
+        }
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}, clock_skew={}s",
+            self.state(),
+            self.clock_skew_seconds
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_skew_beyond_the_limit_is_unhealthy() {
+        let mut health = Health::new(30);
+        health.record_clock_skew(-30);
+        assert!(health.is_healthy());
+        health.record_clock_skew(31);
+        assert!(!health.is_healthy());
+        assert_eq!(health.to_string(), "degraded, clock_skew=31s");
+        // The state holds while the skew keeps changing
+        health.record_clock_skew(40);
+        assert_eq!(health.state(), "degraded");
+    }
+
+    #[test]
+    fn losing_authorization_pauses_until_it_is_granted_again() {
+        let mut health = Health::new(30);
+        health.record_authorization(false);
+        assert!(!health.is_healthy());
+        assert_eq!(health.to_string(), "paused, unauthorized, clock_skew=0s");
+        health.record_authorization(true);
+        assert!(health.is_healthy());
+    }
+}
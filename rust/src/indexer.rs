@@ -0,0 +1,709 @@
+use crate::config;
+use crate::contracts::azos_stability_module::AzosStabilityModuleEvents;
+use crate::rpc::{FailoverClient, LOG_CHUNK_BLOCKS};
+use crate::signer::KeeperSigner;
+use crate::types::provider::StabilityModule;
+use crate::utils::decimal::{i256_to_decimal, u256_to_decimal};
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Subcommand};
+use ethers::prelude::*;
+use ethers::utils::parse_bytes32_string;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// How many of the latest indexed blocks are remembered, to find where a reorg forked from
+const REORG_DEPTH: usize = 64;
+
+/// A stability module log, one per line in the store
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct IndexedEvent {
+    pub block_number: u64,
+    pub block_hash: H256,
+    pub log_index: u64,
+    pub transaction_hash: H256,
+    #[serde(flatten)]
+    pub kind: ModuleEvent,
+}
+
+/// Debts are signed, so they're stored as decimal strings rather than two's complement
+mod signed {
+    use ethers::types::I256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &I256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<I256, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        I256::from_dec_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "event")]
+pub enum ModuleEvent {
+    Expand {
+        amount: U256,
+        burn_amount: U256,
+        target: Address,
+    },
+    Contract {
+        amount: U256,
+        burn_amount: U256,
+        target: Address,
+    },
+    ExpandDebt {
+        #[serde(with = "signed")]
+        previous_debt: I256,
+        #[serde(with = "signed")]
+        new_debt: I256,
+        previous_balance: U256,
+        new_balance: U256,
+    },
+    ContractDebt {
+        #[serde(with = "signed")]
+        previous_debt: I256,
+        #[serde(with = "signed")]
+        new_debt: I256,
+        previous_balance: U256,
+        new_balance: U256,
+    },
+    KeeperFeePaid {
+        amount: U256,
+        keeper: Address,
+        token: Address,
+    },
+    Deposit {
+        amount: U256,
+    },
+    WindDown {
+        treasury: Address,
+        collateral_amount: U256,
+        coin_amount: U256,
+        authorized: Address,
+    },
+    AddAdapter {
+        adapter_name: String,
+        adapter: Address,
+        authorized: Address,
+    },
+    DebtCeilingChange {
+        debt_ceiling: U256,
+    },
+    MaxDeposit {
+        max_deposit: U256,
+    },
+    KeeperFee {
+        new_fee: U256,
+    },
+    ChangeTreasury {
+        new_treasury: Address,
+        authorized: Address,
+    },
+    AddAuthorization {
+        account: Address,
+    },
+    RemoveAuthorization {
+        account: Address,
+    },
+    BurnBalance {
+        amount: U256,
+    },
+}
+
+impl From<AzosStabilityModuleEvents> for ModuleEvent {
+    fn from(event: AzosStabilityModuleEvents) -> Self {
+        match event {
+            AzosStabilityModuleEvents::ExpandFilter(expand) => ModuleEvent::Expand {
+                amount: expand.amount,
+                burn_amount: expand.burn_amount,
+                target: expand.target,
+            },
+            AzosStabilityModuleEvents::ContractFilter(contract) => ModuleEvent::Contract {
+                amount: contract.amount,
+                burn_amount: contract.burn_amount,
+                target: contract.target,
+            },
+            AzosStabilityModuleEvents::ExpandDebtFilter(debt) => ModuleEvent::ExpandDebt {
+                previous_debt: debt.previous_debt,
+                new_debt: debt.new_debt,
+                previous_balance: debt.previous_balance,
+                new_balance: debt.new_balance,
+            },
+            AzosStabilityModuleEvents::ContractDebtFilter(debt) => ModuleEvent::ContractDebt {
+                previous_debt: debt.previous_debt,
+                new_debt: debt.new_debt,
+                previous_balance: debt.previous_balance,
+                new_balance: debt.new_balance,
+            },
+            AzosStabilityModuleEvents::KeeperFeePaidFilter(fee) => ModuleEvent::KeeperFeePaid {
+                amount: fee.amount,
+                keeper: fee.keeper,
+                token: fee.token,
+            },
+            AzosStabilityModuleEvents::DepositFilter(deposit) => ModuleEvent::Deposit {
+                amount: deposit.amount,
+            },
+            AzosStabilityModuleEvents::WindDownFilter(wind_down) => ModuleEvent::WindDown {
+                treasury: wind_down.treasury,
+                collateral_amount: wind_down.collateral_amount,
+                coin_amount: wind_down.coin_amount,
+                authorized: wind_down.authorized,
+            },
+            AzosStabilityModuleEvents::AddAdapterFilter(adapter) => ModuleEvent::AddAdapter {
+                adapter_name: parse_bytes32_string(&adapter.adapter_name)
+                    .unwrap_or_default()
+                    .to_string(),
+                adapter: adapter.adapter,
+                authorized: adapter.authorized,
+            },
+            AzosStabilityModuleEvents::DebtCeilingChangeFilter(change) => {
+                ModuleEvent::DebtCeilingChange {
+                    debt_ceiling: change.debt_ceiling,
+                }
+            }
+            AzosStabilityModuleEvents::MaxDepositFilter(change) => ModuleEvent::MaxDeposit {
+                max_deposit: change.max_deposit,
+            },
+            AzosStabilityModuleEvents::KeeperFeeFilter(change) => ModuleEvent::KeeperFee {
+                new_fee: change.new_fee,
+            },
+            AzosStabilityModuleEvents::ChangeTreasuryFilter(change) => {
+                ModuleEvent::ChangeTreasury {
+                    new_treasury: change.new_treasury,
+                    authorized: change.authorized,
+                }
+            }
+            AzosStabilityModuleEvents::AddAuthorizationFilter(change) => {
+                ModuleEvent::AddAuthorization {
+                    account: change.account,
+                }
+            }
+            AzosStabilityModuleEvents::RemoveAuthorizationFilter(change) => {
+                ModuleEvent::RemoveAuthorization {
+                    account: change.account,
+                }
+            }
+            AzosStabilityModuleEvents::BurnBalanceFilter(burn) => ModuleEvent::BurnBalance {
+                amount: burn.amount,
+            },
+        }
+    }
+}
+
+/// How far the store has indexed, and the hashes of the latest blocks it indexed up to
+#[derive(Default, Deserialize, Serialize)]
+struct Cursor {
+    last_block: Option<u64>,
+    recent_blocks: VecDeque<(u64, H256)>,
+}
+
+/// Indexed events kept as JSONL in a directory, next to a cursor of how far they go
+pub struct EventStore {
+    dir: PathBuf,
+    events: Vec<IndexedEvent>,
+    cursor: Cursor,
+}
+
+impl EventStore {
+    /**
+     * Opens the store in `dir`, creating it when it doesn't exist yet.
+     */
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Unable to create {}", dir.display()))?;
+        let mut store = EventStore {
+            dir: dir.to_path_buf(),
+            events: vec![],
+            cursor: Cursor::default(),
+        };
+        if let Ok(file) = std::fs::File::open(store.events_path()) {
+            for (index, line) in std::io::BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                store.events.push(
+                    serde_json::from_str(&line)
+                        .with_context(|| format!("Invalid indexed event on line {}", index + 1))?,
+                );
+            }
+        }
+        if let Ok(cursor) = std::fs::read_to_string(store.cursor_path()) {
+            store.cursor = serde_json::from_str(&cursor).context("Invalid index cursor")?;
+        }
+        Ok(store)
+    }
+
+    fn events_path(&self) -> PathBuf {
+        self.dir.join("events.jsonl")
+    }
+
+    fn cursor_path(&self) -> PathBuf {
+        self.dir.join("cursor.json")
+    }
+
+    pub fn last_block(&self) -> Option<u64> {
+        self.cursor.last_block
+    }
+
+    pub fn events(&self) -> &[IndexedEvent] {
+        &self.events
+    }
+
+    fn save_cursor(&self) -> Result<()> {
+        // Written aside and renamed, a crash mid-write mustn't lose track of what's indexed
+        let path = self.cursor_path();
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_string(&self.cursor)?)?;
+        std::fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    /**
+     * Appends the events of the blocks up to `head`, which is remembered for spotting reorgs.
+     */
+    fn commit(&mut self, events: Vec<IndexedEvent>, head: (u64, H256)) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.events_path())?;
+        for event in &events {
+            writeln!(file, "{}", serde_json::to_string(event)?)?;
+        }
+        self.events.extend(events);
+        self.cursor.last_block = Some(head.0);
+        self.cursor.recent_blocks.push_back(head);
+        while self.cursor.recent_blocks.len() > REORG_DEPTH {
+            self.cursor.recent_blocks.pop_front();
+        }
+        self.save_cursor()
+    }
+
+    /**
+     * Forgets everything after `block_number`, or everything when there's no block to keep.
+     */
+    fn rollback(&mut self, block_number: Option<u64>) -> Result<()> {
+        let kept = |number: u64| block_number.is_some_and(|last| number <= last);
+        self.events.retain(|event| kept(event.block_number));
+        self.cursor
+            .recent_blocks
+            .retain(|(number, _)| kept(*number));
+        self.cursor.last_block = block_number;
+        let mut file = std::fs::File::create(self.events_path())?;
+        for event in &self.events {
+            writeln!(file, "{}", serde_json::to_string(event)?)?;
+        }
+        self.save_cursor()
+    }
+
+    /**
+     * The module's debt after every expansion and contraction, in the system coin's base units.
+     */
+    pub fn debt_over_time(&self) -> Vec<(u64, I256)> {
+        self.events
+            .iter()
+            .filter_map(|event| match event.kind {
+                ModuleEvent::ExpandDebt { new_debt, .. }
+                | ModuleEvent::ContractDebt { new_debt, .. } => {
+                    Some((event.block_number, new_debt))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /**
+     * Fees the module has paid `keeper`, totalled per token.
+     */
+    pub fn keeper_fees_paid(&self, keeper: Address) -> BTreeMap<Address, U256> {
+        let mut fees = BTreeMap::new();
+        for event in &self.events {
+            if let ModuleEvent::KeeperFeePaid {
+                amount,
+                keeper: paid_to,
+                token,
+            } = event.kind
+            {
+                if paid_to == keeper {
+                    *fees.entry(token).or_insert_with(U256::zero) += amount;
+                }
+            }
+        }
+        fees
+    }
+}
+
+/// Keeps an `EventStore` in step with the stability module's logs
+/**
+ * The stability module's logs from `from_block` to `to_block` inclusive, fetched `LOG_CHUNK_BLOCKS` at a time so
+ * long ranges stay within what nodes serve in one `eth_getLogs`.
+ */
+pub async fn fetch_events<M: Middleware + 'static>(
+    stability_module: &StabilityModule<M>,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<IndexedEvent>> {
+    let mut events = vec![];
+    let mut chunk_start = from_block;
+    while chunk_start <= to_block {
+        let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(to_block);
+        debug!(
+            from_block = chunk_start,
+            to_block = chunk_end,
+            "Fetching module logs"
+        );
+        let logs = stability_module
+            .events()
+            .from_block(chunk_start)
+            .to_block(chunk_end)
+            .query_with_meta()
+            .await?;
+        events.extend(logs.into_iter().map(|(event, meta)| IndexedEvent {
+            block_number: meta.block_number.as_u64(),
+            block_hash: meta.block_hash,
+            log_index: meta.log_index.as_u64(),
+            transaction_hash: meta.transaction_hash,
+            kind: event.into(),
+        }));
+        chunk_start = chunk_end + 1;
+    }
+    Ok(events)
+}
+
+pub struct Indexer<M> {
+    provider: Arc<M>,
+    stability_module: StabilityModule<M>,
+    store: EventStore,
+}
+
+impl<M: Middleware + 'static> Indexer<M> {
+    pub fn new(provider: Arc<M>, stability_module_address: Address, store: EventStore) -> Self {
+        Indexer {
+            stability_module: StabilityModule::new(stability_module_address, provider.clone()),
+            provider,
+            store,
+        }
+    }
+
+    pub fn store(&self) -> &EventStore {
+        &self.store
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<Option<H256>> {
+        Ok(self
+            .provider
+            .get_block(block_number)
+            .await
+            .map_err(|error| anyhow!("Unable to read block {block_number}: {error}"))?
+            .and_then(|block| block.hash))
+    }
+
+    /**
+     * Rolls the store back to the latest block it shares with the node, when the chain has reorganised under it.
+     */
+    async fn unwind_reorg(&mut self) -> Result<()> {
+        let recent_blocks = self.store.cursor.recent_blocks.clone();
+        for (block_number, hash) in recent_blocks.iter().rev() {
+            if self.block_hash(*block_number).await? == Some(*hash) {
+                if self.store.last_block() != Some(*block_number) {
+                    warn!(
+                        block = *block_number,
+                        "Chain reorganised, rolling the index back"
+                    );
+                    self.store.rollback(Some(*block_number))?;
+                }
+                return Ok(());
+            }
+        }
+        // Deeper than we remember, index the remembered blocks again from scratch
+        if let Some((oldest, _)) = recent_blocks.front() {
+            let keep = oldest.checked_sub(1);
+            match keep {
+                Some(block_number) => warn!(
+                    block = block_number,
+                    "Chain reorganised past every remembered block, rolling the index back"
+                ),
+                None => warn!(
+                    "Chain reorganised past every remembered block, indexing again from scratch"
+                ),
+            }
+            self.store.rollback(keep)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Indexes everything from where the store left off, or `from_block` when it's empty, up to the latest block.
+     * Returns how many events were added.
+     */
+    pub async fn sync(&mut self, from_block: u64) -> Result<usize> {
+        self.unwind_reorg().await?;
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|error| anyhow!("Unable to read the block number: {error}"))?
+            .as_u64();
+        let mut chunk_start = self.store.last_block().map_or(from_block, |last| last + 1);
+        let mut added = 0;
+        while chunk_start <= head {
+            let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(head);
+            let events = fetch_events(&self.stability_module, chunk_start, chunk_end).await?;
+            let hash = self
+                .block_hash(chunk_end)
+                .await?
+                .ok_or_else(|| anyhow!("Block {chunk_end} not found"))?;
+            added += events.len();
+            self.store.commit(events, (chunk_end, hash))?;
+            chunk_start = chunk_end + 1;
+        }
+        if added > 0 {
+            info!(events = added, block = head, "Indexed module events");
+        }
+        Ok(added)
+    }
+
+    /**
+     * Backfills from `from_block`, then follows the chain, polling every `poll_interval`.
+     */
+    pub async fn follow(&mut self, from_block: u64, poll_interval: Duration) {
+        loop {
+            if let Err(error) = self.sync(from_block).await {
+                error!(%error, "Indexing failed");
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct IndexArgs {
+    /// Directory the events are kept in
+    #[arg(long, default_value = "index")]
+    store: PathBuf,
+    #[command(subcommand)]
+    command: IndexCommand,
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// Index from where the store left off, or from --from-block when it's empty
+    Sync {
+        #[arg(long, default_value_t = 0)]
+        from_block: u64,
+        /// Keep following the chain after catching up
+        #[arg(long)]
+        follow: bool,
+    },
+    /// The module's debt after every expansion and contraction
+    Debt,
+    /// Fees the module has paid a keeper, ours by default
+    Fees {
+        #[arg(long)]
+        keeper: Option<Address>,
+    },
+}
+
+/**
+ * Brings the store up to date, or answers a query from it.  Queries don't reach an endpoint, and only need the pair
+ * configured, along with the signer when asking for the keeper's own fees.
+ */
+pub async fn command(args: IndexArgs) -> Result<()> {
+    let store = EventStore::open(&args.store)?;
+    match args.command {
+        IndexCommand::Sync { from_block, follow } => {
+            let provider = Arc::new(Provider::new(FailoverClient::new(
+                &config::generate_rpc_urls(),
+            )?));
+            let stability_module_address = config::generate_stability_module_address();
+            let mut indexer = Indexer::new(provider, stability_module_address, store);
+            if follow {
+                let poll_interval = Duration::from_millis(config::DELAY_BETWEEN_CHECKS_MS as u64);
+                indexer.follow(from_block, poll_interval).await;
+                return Ok(());
+            }
+            let added = indexer.sync(from_block).await?;
+            println!(
+                "Indexed {added} events, {} in total up to block {:?}",
+                indexer.store().events().len(),
+                indexer.store().last_block()
+            );
+        }
+        IndexCommand::Debt => {
+            let system_coin_decimals = config::generate_strategy_config()
+                .token_pair
+                .token_1
+                .decimals;
+            for (block_number, debt) in store.debt_over_time() {
+                println!(
+                    "{block_number}  {}",
+                    i256_to_decimal(debt, system_coin_decimals)
+                );
+            }
+        }
+        IndexCommand::Fees { keeper } => {
+            let keeper = match keeper {
+                Some(keeper) => keeper,
+                None => KeeperSigner::from_config(&config::generate_signer_config())?.address(),
+            };
+            let token_pair = config::generate_strategy_config().token_pair;
+            for (token, amount) in store.keeper_fees_paid(keeper) {
+                let configured = [&token_pair.token_0, &token_pair.token_1]
+                    .into_iter()
+                    .find(|configured| configured.address == token);
+                match configured {
+                    Some(configured) => println!(
+                        "{}  {}",
+                        configured.symbol,
+                        u256_to_decimal(amount, configured.decimals)
+                    ),
+                    None => println!("{token:?}  {amount}"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::azos_stability_module::{DepositFilter, KeeperFeePaidFilter};
+    use crate::test_utils::MockChain;
+
+    const MODULE: Address = H160([0x20; 20]);
+
+    fn topic(value: impl Into<U256>) -> H256 {
+        H256::from_uint(&value.into())
+    }
+
+    fn deposit(amount: u64) -> Log {
+        Log {
+            address: MODULE,
+            topics: vec![DepositFilter::signature(), topic(amount)],
+            ..Default::default()
+        }
+    }
+
+    fn keeper_fee_paid(amount: u64, keeper: Address, token: Address) -> Log {
+        Log {
+            address: MODULE,
+            topics: vec![
+                KeeperFeePaidFilter::signature(),
+                topic(amount),
+                H256::from(keeper),
+                H256::from(token),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn temporary_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("azos-index-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn reorgs_roll_back_to_the_fork() {
+        let chain = Arc::new(MockChain::default());
+        chain.mine(H256::repeat_byte(0xa0), vec![]);
+        chain.mine(H256::repeat_byte(0xa1), vec![deposit(100)]);
+        chain.mine(H256::repeat_byte(0xa2), vec![deposit(200)]);
+        let dir = temporary_dir("reorg");
+        let mut indexer = Indexer::new(chain.provider(), MODULE, EventStore::open(&dir).unwrap());
+
+        assert_eq!(indexer.sync(0).await.unwrap(), 2);
+        assert_eq!(indexer.store().last_block(), Some(2));
+
+        // Block 2 is replaced, and a block is mined on top of the new one
+        chain.reorg(1);
+        chain.mine(H256::repeat_byte(0xb2), vec![deposit(300)]);
+        chain.mine(H256::repeat_byte(0xb3), vec![]);
+        assert_eq!(indexer.sync(0).await.unwrap(), 1);
+
+        let amounts: Vec<ModuleEvent> = indexer
+            .store()
+            .events()
+            .iter()
+            .map(|event| event.kind.clone())
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                ModuleEvent::Deposit {
+                    amount: U256::from(100)
+                },
+                ModuleEvent::Deposit {
+                    amount: U256::from(300)
+                },
+            ]
+        );
+        assert_eq!(
+            indexer.store().events()[1].block_hash,
+            H256::repeat_byte(0xb2)
+        );
+
+        // What's on disk matches, so a restart picks up where this left off
+        let reopened = EventStore::open(&dir).unwrap();
+        assert_eq!(reopened.events(), indexer.store().events());
+        assert_eq!(reopened.last_block(), Some(3));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reorgs_of_the_first_block_start_over() {
+        let chain = Arc::new(MockChain::default());
+        chain.mine(H256::repeat_byte(0xa0), vec![deposit(100)]);
+        let dir = temporary_dir("reorg-genesis");
+        let mut indexer = Indexer::new(chain.provider(), MODULE, EventStore::open(&dir).unwrap());
+        assert_eq!(indexer.sync(0).await.unwrap(), 1);
+
+        chain.reorg(1);
+        chain.mine(H256::repeat_byte(0xb0), vec![deposit(300)]);
+        chain.mine(H256::repeat_byte(0xb1), vec![]);
+        assert_eq!(indexer.sync(0).await.unwrap(), 1);
+
+        let events = indexer.store().events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].block_hash, H256::repeat_byte(0xb0));
+        assert_eq!(indexer.store().last_block(), Some(1));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fees_are_totalled_for_one_keeper() {
+        let (keeper, other_keeper) = (Address::repeat_byte(0x0c), Address::repeat_byte(0x0d));
+        let (usdc, zai) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+        let chain = Arc::new(MockChain::default());
+        chain.mine(
+            H256::repeat_byte(0xa0),
+            vec![
+                keeper_fee_paid(5, keeper, usdc),
+                keeper_fee_paid(7, other_keeper, usdc),
+            ],
+        );
+        chain.mine(
+            H256::repeat_byte(0xa1),
+            vec![
+                keeper_fee_paid(3, keeper, usdc),
+                keeper_fee_paid(2, keeper, zai),
+            ],
+        );
+        let dir = temporary_dir("fees");
+        let mut indexer = Indexer::new(chain.provider(), MODULE, EventStore::open(&dir).unwrap());
+        indexer.sync(0).await.unwrap();
+
+        let fees = indexer.store().keeper_fees_paid(keeper);
+        assert_eq!(fees[&usdc], U256::from(8));
+        assert_eq!(fees[&zai], U256::from(2));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,1518 @@
+use crate::adapters::{Adapter, AdapterRegistry};
+use crate::config::Config;
+use crate::contracts::azos_stability_module::{AzosStabilityModule, AzosStabilityModuleErrors};
+use crate::contracts::uniswap_v2_factory::UniswapV2Factory;
+use crate::contracts::uniswap_v2_router02::UniswapV2Router02;
+use crate::contracts::uniswap_v3_factory::UniswapV3Factory;
+use crate::contracts::uniswap_v3_quoter_v2::UniswapV3QuoterV2;
+use crate::governance::ModuleView;
+use crate::health::Health;
+use crate::indexer::{self, IndexedEvent, ModuleEvent};
+use crate::notifier::{Alert, Notifier};
+use crate::relay::BundleRelay;
+use crate::routing::RouteFinder;
+use crate::rpc::{self, FailoverClient, QuorumReader};
+use crate::signer::KeeperSigner;
+use crate::types::keeper::{
+    best_action, by_direction, common_input_size, split_correction, KeeperAction,
+};
+use crate::types::provider::{
+    KeeperProvider, StabilityModule, UniswapRouter, UniswapV3PoolFactory, UniswapV3Quoter,
+};
+use crate::types::swap::SwapDetails;
+use crate::types::token::TokenPair;
+use crate::types::venue::{PairVenue, Venue, VenueSelection};
+use crate::utils::decimal::{
+    apply_slippage_bps, decimal_is_within_allowed_range, decimal_to_u256, u256_to_decimal,
+};
+use crate::utils::time::{clock_skew_seconds, get_swap_deadline};
+use crate::venues;
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::AbiEncode;
+use ethers::prelude::*;
+use ethers::utils::format_ether;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::{fmt, thread, time};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
+
+pub type StabilityModuleCall<M = KeeperProvider> = FunctionCall<Arc<M>, M, ()>;
+
+pub fn generate_delegate_call_data(
+    adapter: &dyn Adapter,
+    swap_details: &SwapDetails,
+    slippage_bps: u32,
+    deadline: U256,
+) -> Result<Bytes> {
+    let swap_details = SwapDetails {
+        amount_to_buy_min: apply_slippage_bps(swap_details.amount_to_buy_min, slippage_bps),
+        ..swap_details.clone()
+    };
+    adapter.encode_swap_data(&swap_details, deadline)
+}
+
+pub fn generate_stability_module_call<M: Middleware>(
+    stability_module: &StabilityModule<M>,
+    adapter: &dyn Adapter,
+    action_to_take: &KeeperAction,
+    swap_details: &SwapDetails,
+    delegate_call_data: Bytes,
+) -> StabilityModuleCall<M> {
+    let adapter_name = adapter.name_as_bytes32();
+    let adapter_name_as_hex = adapter_name.encode_hex();
+
+    if let KeeperAction::ContractAndSell(_) = &action_to_take {
+        debug!(
+            "CONTRACT_AND_SELL, adapter_name={adapter_name_as_hex:?}, data={delegate_call_data}"
+        );
+        stability_module.contract_and_sell(adapter_name, delegate_call_data)
+    } else {
+        let mint_amount = decimal_to_u256(
+            swap_details.amount_to_sell,
+            swap_details.token_to_sell.decimals,
+        );
+        debug!("EXPAND_AND_BUY CALL, adapter_name={adapter_name_as_hex:?}, data={delegate_call_data}, mint_amount={mint_amount}");
+        stability_module.expand_and_buy(adapter_name, delegate_call_data, mint_amount)
+    }
+}
+
+/// The stability module turned the keeper's account away, nothing sent from it goes through until it's authorized
+#[derive(Debug)]
+pub struct NotAuthorizedError;
+
+impl fmt::Display for NotAuthorizedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the stability module does not authorize the keeper's account"
+        )
+    }
+}
+
+impl std::error::Error for NotAuthorizedError {}
+
+/// Owns every handle the keeper needs, from quoting the venues to submitting stability module calls
+pub struct Keeper<M = KeeperProvider> {
+    config: Config,
+    provider: Arc<M>,
+    uniswap_router: UniswapRouter<M>,
+    route_finder: RouteFinder,
+    uniswap_v3_factory: Option<UniswapV3PoolFactory<M>>,
+    uniswap_v3_quoter: Option<UniswapV3Quoter<M>>,
+    adapter_registry: AdapterRegistry,
+    stability_module: StabilityModule<M>,
+    bundle_relay: Option<BundleRelay>,
+    quorum_reader: Option<Arc<QuorumReader>>,
+    health: Health,
+    module_view: ModuleView,
+    module_view_block: Option<u64>, // The block the view is up to date with, None until the first tick
+    authorization_required: bool, // Set from config, or once the module has refused the keeper's account
+    authorized: bool,
+    notifier: Option<Arc<Notifier>>,
+    below_balance_floor: bool,
+    out_of_band_since: HashMap<String, u64>, // The first block each pair was seen out of range, while it stays so
+    tick_id: u64, // Counts ticks since startup, to tell apart the log lines of repeated ticks on a block
+    reported_health: Option<&'static str>, // The health state last logged at info, logged again once it changes
+}
+
+/**
+ * The configured endpoints, signing with the configured signer.  Failovers are alerted through `notifier`, when
+ * given.
+ */
+pub fn connect(config: &Config, notifier: Option<Arc<Notifier>>) -> Result<KeeperProvider> {
+    // Provider, Wallet, and Signer Client
+    let mut client = FailoverClient::new(&config.rpc_urls)?;
+    if let Some(notifier) = notifier {
+        client = client.with_notifier(notifier);
+    }
+    let provider = Provider::new(client);
+    let keeper_signer = KeeperSigner::from_config(&config.keeper_signer)?
+        // FIXME: Make this chain configured from env var
+        .with_chain_id(Chain::Sepolia);
+    info!("Keeper address: {:?}", keeper_signer.address());
+    Ok(SignerMiddleware::new(provider, keeper_signer))
+}
+
+/**
+ * The notifier for the configured alerts, if any.
+ */
+fn configured_notifier(config: &Config) -> Option<Arc<Notifier>> {
+    config
+        .alerts
+        .as_ref()
+        .map(|alerts| Arc::new(Notifier::new(alerts)))
+}
+
+impl Keeper<KeeperProvider> {
+    /**
+     * Connects to the configured endpoints with the configured signer, then sets up as `with_provider` does.
+     */
+    pub async fn new(config: Config) -> Result<Self> {
+        // Failovers share the keeper's notifier, and so its dedup and rate limit
+        let notifier = configured_notifier(&config);
+        let provider = connect(&config, notifier.clone())?;
+        Keeper::with_notifier(config, Arc::new(provider), notifier).await
+    }
+}
+
+impl<M: Middleware + 'static> Keeper<M> {
+    /**
+     * Runs the keeper over any middleware stack, and checks the configured adapters against the stability module.
+     * Transactions are sent from the middleware's default sender.
+     */
+    pub async fn with_provider(config: Config, provider: Arc<M>) -> Result<Self> {
+        let notifier = configured_notifier(&config);
+        Keeper::with_notifier(config, provider, notifier).await
+    }
+
+    /**
+     * As `with_provider`, alerting through `notifier` rather than one of its own.
+     */
+    async fn with_notifier(
+        config: Config,
+        provider: Arc<M>,
+        notifier: Option<Arc<Notifier>>,
+    ) -> Result<Self> {
+        // Critical reads, when a quorum is configured
+        let quorum_reader = config
+            .rpc_quorum
+            .map(|quorum| rpc::quorum_reader(&config.rpc_urls, quorum))
+            .transpose()?;
+
+        // Uniswap
+        let uniswap_router =
+            UniswapV2Router02::new(config.uniswap_router_address, provider.clone());
+        let uniswap_factory =
+            UniswapV2Factory::new(config.uniswap_factory_address, provider.clone());
+        let route_finder =
+            RouteFinder::build(&config, &provider, &uniswap_factory, quorum_reader.clone()).await?;
+
+        // Stability Module
+        let stability_module =
+            AzosStabilityModule::new(config.stability_module_address, provider.clone());
+
+        // Adapters, which must match what the stability module has registered
+        let adapter_registry = AdapterRegistry::from_config(&config.adapters)?;
+        adapter_registry.verify(&stability_module).await?;
+        let adapter_names: Vec<&str> = adapter_registry
+            .iter()
+            .map(|adapter| adapter.name())
+            .collect();
+        let module_view = ModuleView::load(&stability_module, &adapter_names).await?;
+
+        // Uniswap V3, when configured
+        let uniswap_v3_factory = config
+            .uniswap_v3
+            .as_ref()
+            .map(|v3| UniswapV3Factory::new(v3.factory_address, provider.clone()));
+        let uniswap_v3_quoter = config
+            .uniswap_v3
+            .as_ref()
+            .map(|v3| UniswapV3QuoterV2::new(v3.quoter_address, provider.clone()));
+
+        // Private submission, when configured
+        let bundle_relay = config
+            .bundle_relay
+            .as_ref()
+            .map(|relay| BundleRelay::new(relay.url.clone(), relay.max_blocks));
+
+        let health = Health::new(config.max_clock_skew_seconds);
+        let authorization_required = config.authorization_required;
+        let mut keeper = Keeper {
+            config,
+            provider,
+            uniswap_router,
+            route_finder,
+            uniswap_v3_factory,
+            uniswap_v3_quoter,
+            adapter_registry,
+            stability_module,
+            bundle_relay,
+            quorum_reader,
+            health,
+            module_view,
+            module_view_block: None,
+            authorization_required,
+            authorized: true,
+            notifier,
+            below_balance_floor: false,
+            out_of_band_since: HashMap::new(),
+            tick_id: 0,
+            reported_health: None,
+        };
+        keeper.check_authorization().await?;
+        Ok(keeper)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn provider(&self) -> &Arc<M> {
+        &self.provider
+    }
+
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
+
+    pub fn module_view(&self) -> &ModuleView {
+        &self.module_view
+    }
+
+    pub fn is_authorized(&self) -> bool {
+        self.authorized
+    }
+
+    /**
+     * Reads whether the module authorizes the keeper's account, when it's known to require that.  Providers without
+     * an account, like the read-only one `simulate` runs over, have nothing to check.
+     */
+    pub async fn check_authorization(&mut self) -> Result<()> {
+        if !self.authorization_required {
+            return Ok(());
+        }
+        let Some(account) = self.provider.default_sender() else {
+            warn!("The keeper has no account, so its authorization on the stability module can't be checked");
+            return Ok(());
+        };
+        let authorized = self
+            .stability_module
+            .authorized_accounts_with_account(account)
+            .call()
+            .await?;
+        self.record_authorization(authorized).await;
+        Ok(())
+    }
+
+    async fn record_authorization(&mut self, authorized: bool) {
+        if authorized && !self.authorized {
+            info!("The keeper's account is authorized on the stability module, resuming");
+            self.alert(Alert::Resumed {
+                reason: String::from("the keeper's account is authorized on the stability module"),
+            })
+            .await;
+        } else if !authorized && self.authorized {
+            error!("The keeper's account is not authorized on the stability module, pausing until it is");
+            self.alert(Alert::Paused {
+                reason: String::from(
+                    "the keeper's account is not authorized on the stability module",
+                ),
+            })
+            .await;
+        }
+        self.authorized = authorized;
+        self.health.record_authorization(authorized);
+    }
+
+    async fn alert(&self, alert: Alert) {
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(alert).await;
+        }
+    }
+
+    /**
+     * Brings the module view up to `block_number` from the governance events since it was last updated.  The first
+     * time round it's read afresh instead, nothing having been watched since startup.  The keeper's authorization is
+     * read again whenever its account is granted or stripped of it.
+     */
+    pub async fn refresh_module_view(&mut self, block_number: u64) -> Result<()> {
+        let was_wound_down = self.module_view.wound_down;
+        match self.module_view_block {
+            None => {
+                self.check_authorization().await?;
+                let adapter_names: Vec<String> =
+                    self.module_view.adapters.keys().cloned().collect();
+                let adapter_names: Vec<&str> = adapter_names.iter().map(String::as_str).collect();
+                self.module_view = ModuleView::load(&self.stability_module, &adapter_names).await?;
+            }
+            Some(last_block) if block_number > last_block => {
+                let events =
+                    indexer::fetch_events(&self.stability_module, last_block + 1, block_number)
+                        .await?;
+                let keeper_account = self.provider.default_sender();
+                let mut authorization_changed = false;
+                for IndexedEvent { kind: event, .. } in events {
+                    if let ModuleEvent::AddAuthorization { account }
+                    | ModuleEvent::RemoveAuthorization { account } = &event
+                    {
+                        authorization_changed |= Some(*account) == keeper_account;
+                    }
+                    if let Some(change) = self.module_view.apply(&event) {
+                        info!("Stability module governance changed, {change}");
+                    }
+                }
+                if authorization_changed {
+                    self.check_authorization().await?;
+                }
+            }
+            Some(_) => {}
+        }
+        self.module_view_block = Some(block_number);
+        if self.module_view.wound_down {
+            self.health.record_wind_down();
+            if !was_wound_down {
+                self.alert(Alert::Paused {
+                    reason: String::from("the stability module has been wound down"),
+                })
+                .await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn quote_venue(
+        &self,
+        token_pair: &TokenPair,
+        pair_venue: &PairVenue,
+    ) -> Result<KeeperAction> {
+        let config = &self.config;
+        let (dex_price, amount_to_sell, amount_to_buy_min, path) = match pair_venue.venue {
+            Venue::UniswapV2 => {
+                venues::uniswap_v2::get_swap_details(
+                    config,
+                    &self.provider,
+                    &self.uniswap_router,
+                    &self.route_finder,
+                    token_pair,
+                )
+                .await?
+            }
+            Venue::UniswapV3 { fee } => {
+                venues::uniswap_v3::get_swap_details(
+                    config,
+                    &self.provider,
+                    self.uniswap_v3_factory
+                        .as_ref()
+                        .expect("Uniswap V3 pair configured without a V3 factory"),
+                    token_pair,
+                    fee,
+                )
+                .await?
+            }
+            Venue::CurveStableSwap { pool, coins } => {
+                venues::curve::get_swap_details(config, &self.provider, token_pair, pool, &coins)
+                    .await?
+            }
+        };
+
+        // Venues that couldn't size a swap quote nothing to sell, there's nothing to act on either way
+        let action = if decimal_is_within_allowed_range(dex_price, config.ratio_range_allowed)
+            || amount_to_sell.is_zero()
+        {
+            KeeperAction::None(SwapDetails {
+                dex_price,
+                venue: pair_venue.venue.clone(),
+                adapter_name: pair_venue.adapter_name.clone(),
+                token_to_sell: token_pair.token_0.clone(),
+                amount_to_sell: Decimal::ZERO,
+                token_to_buy: token_pair.token_1.clone(),
+                amount_to_buy_min: Decimal::ZERO,
+                path: vec![],
+            })
+        } else if dex_price > Decimal::ONE {
+            // System coin is worth more than stable coin
+            KeeperAction::ExpandAndBuy(SwapDetails {
+                dex_price,
+                venue: pair_venue.venue.clone(),
+                adapter_name: pair_venue.adapter_name.clone(),
+                token_to_sell: token_pair.token_1.clone(),
+                amount_to_sell,
+                token_to_buy: token_pair.token_0.clone(),
+                amount_to_buy_min,
+                path,
+            })
+        } else {
+            // Stable coin is worth more than system coin
+            KeeperAction::ContractAndSell(SwapDetails {
+                dex_price,
+                venue: pair_venue.venue.clone(),
+                adapter_name: pair_venue.adapter_name.clone(),
+                token_to_sell: token_pair.token_0.clone(),
+                amount_to_sell,
+                token_to_buy: token_pair.token_1.clone(),
+                amount_to_buy_min,
+                path,
+            })
+        };
+        Ok(action)
+    }
+
+    /**
+     * Quotes every venue of the pair and returns the actions worth taking, as picked by the venue selection.
+     */
+    pub async fn evaluate_pair(&self, token_pair: &TokenPair) -> Result<Vec<KeeperAction>> {
+        self.select_actions(self.quote_pair(token_pair).await?)
+            .await
+    }
+
+    /**
+     * Narrows the actions quoted across a pair's venues down to the ones to execute, for each direction either the
+     * venue giving the most for the same amount, or one correction split across them all.
+     */
+    async fn select_actions(&self, quotes: Vec<KeeperAction>) -> Result<Vec<KeeperAction>> {
+        let mut selected = vec![];
+        for actions in by_direction(quotes) {
+            match self.config.venue_selection {
+                VenueSelection::Split => selected.extend(split_correction(actions)),
+                VenueSelection::Best if actions.len() <= 1 => selected.extend(actions),
+                VenueSelection::Best => {
+                    let Some(amount_to_sell) = common_input_size(&actions) else {
+                        continue;
+                    };
+                    let mut outputs = vec![];
+                    for action in &actions {
+                        let swap_details = SwapDetails {
+                            amount_to_sell,
+                            ..action.swap_details().clone()
+                        };
+                        let output = self.quote_amount_out(&swap_details).await?;
+                        debug!(
+                            "{:?} gives {output} for {amount_to_sell}",
+                            swap_details.venue
+                        );
+                        outputs.push(output);
+                    }
+                    selected.extend(best_action(actions, &outputs));
+                }
+            }
+        }
+        Ok(selected)
+    }
+
+    /**
+     * Quotes every venue of the pair, including those needing no action.  A venue that can't be quoted fails the
+     * whole pair, acting on the others alone could pick the wrong venue.
+     */
+    async fn quote_pair(&self, token_pair: &TokenPair) -> Result<Vec<KeeperAction>> {
+        let mut actions = vec![];
+        for pair_venue in &token_pair.venues {
+            let action = self.quote_venue(token_pair, pair_venue).await?;
+            let swap_details = action.swap_details();
+            if let KeeperAction::None(_) = action {
+                info!(
+                    "There was no favourable swap to make on {:?} for dex_price of {}",
+                    swap_details.venue, swap_details.dex_price
+                );
+            } else {
+                debug!(
+                    "Quoted {} on {:?}, dex_price={}, amount_to_sell={}, amount_to_buy_min={}",
+                    token_pair.symbol,
+                    swap_details.venue,
+                    swap_details.dex_price,
+                    swap_details.amount_to_sell,
+                    swap_details.amount_to_buy_min
+                );
+            }
+            actions.push(action);
+        }
+        Ok(actions)
+    }
+
+    /**
+     * The price the pair trades at on each of its venues.
+     */
+    pub async fn venue_prices(&self, token_pair: &TokenPair) -> Result<Vec<(Venue, Decimal)>> {
+        let mut prices = vec![];
+        for pair_venue in &token_pair.venues {
+            let action = self.quote_venue(token_pair, pair_venue).await?;
+            prices.push((pair_venue.venue.clone(), action.swap_details().dex_price));
+        }
+        Ok(prices)
+    }
+
+    /**
+     * The pair's price once the action's swap has gone through, for the venues that can be simulated.
+     */
+    pub async fn price_after(
+        &self,
+        token_pair: &TokenPair,
+        action: &KeeperAction,
+    ) -> Result<Option<Decimal>> {
+        let swap_details = action.swap_details();
+        match swap_details.venue {
+            Venue::UniswapV2 => Ok(Some(
+                venues::uniswap_v2::price_after_swap(
+                    &self.config,
+                    &self.provider,
+                    &self.route_finder,
+                    token_pair,
+                    swap_details,
+                )
+                .await?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /**
+     * What the swap's venue would give for it right now.
+     */
+    async fn quote_amount_out(&self, swap_details: &SwapDetails) -> Result<Decimal> {
+        match &swap_details.venue {
+            Venue::UniswapV2 => {
+                venues::uniswap_v2::quote_amount_out(&self.uniswap_router, swap_details).await
+            }
+            Venue::CurveStableSwap { pool, coins } => {
+                venues::curve::quote_amount_out(&self.provider, *pool, coins, swap_details).await
+            }
+            Venue::UniswapV3 { fee } => {
+                let uniswap_v3_quoter = self
+                    .uniswap_v3_quoter
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("Uniswap V3 pair configured without a V3 quoter"))?;
+                venues::uniswap_v3::quote_amount_out(uniswap_v3_quoter, *fee, swap_details).await
+            }
+        }
+    }
+
+    /**
+     * Re-quotes the swap just before sending it, and refuses if the venue can no longer meet our minimum.
+     */
+    async fn check_fresh_quote(&self, swap_details: &SwapDetails, slippage_bps: u32) -> Result<()> {
+        let amount_to_buy_min = apply_slippage_bps(swap_details.amount_to_buy_min, slippage_bps);
+        let fresh_amount_out = self.quote_amount_out(swap_details).await?;
+        debug!(
+            "Fresh quote.. amount_out={fresh_amount_out}, amount_to_buy_min={amount_to_buy_min}"
+        );
+        if fresh_amount_out < amount_to_buy_min {
+            bail!(
+                "fresh quote of {fresh_amount_out} {} is below the minimum of {amount_to_buy_min}",
+                swap_details.token_to_buy.symbol
+            );
+        }
+        Ok(())
+    }
+
+    /**
+     * The module's debt as agreed by a quorum of endpoints, so one bad node can't drive an expansion.
+     */
+    async fn read_module_debt(&self, quorum_reader: &Arc<QuorumReader>) -> Result<I256> {
+        let stability_module =
+            AzosStabilityModule::new(self.config.stability_module_address, quorum_reader.clone());
+        Ok(stability_module.get_debt().call().await?)
+    }
+
+    /**
+     * Checks that still hold right before submitting: the adapter is still registered where we expect, a mint stays
+     * under the debt ceiling with the debt agreed by quorum when one is configured, and the venue still quotes at
+     * least our minimum.
+     */
+    async fn preflight(&self, token_pair: &TokenPair, action_to_take: &KeeperAction) -> Result<()> {
+        let swap_details = action_to_take.swap_details();
+        if let (Some(adapter), Some(registered)) = (
+            self.adapter_registry.get(&swap_details.adapter_name),
+            self.module_view.adapters.get(&swap_details.adapter_name),
+        ) {
+            if *registered != adapter.address() {
+                bail!(
+                    "adapter {} was registered again at {registered:?}, the configured {:?} is out of date",
+                    swap_details.adapter_name,
+                    adapter.address()
+                );
+            }
+        }
+        if let KeeperAction::ExpandAndBuy(_) = action_to_take {
+            let debt = match &self.quorum_reader {
+                Some(quorum_reader) => {
+                    let debt = self
+                        .read_module_debt(quorum_reader)
+                        .await
+                        .map_err(|error| anyhow!("endpoints disagree on module debt: {error}"))?;
+                    debug!("Module debt agreed by quorum: {debt}");
+                    debt
+                }
+                None => self.stability_module.get_debt().call().await?,
+            };
+            let mint_amount = decimal_to_u256(
+                swap_details.amount_to_sell,
+                swap_details.token_to_sell.decimals,
+            );
+            if debt + I256::from_raw(mint_amount) > I256::from_raw(self.module_view.debt_ceiling) {
+                bail!(
+                    "minting {mint_amount} onto a debt of {debt} would pass the debt ceiling of {}",
+                    self.module_view.debt_ceiling
+                );
+            }
+        }
+        self.check_fresh_quote(swap_details, token_pair.slippage_bps)
+            .await
+    }
+
+    /**
+     * Builds the `contractAndSell`/`expandAndBuy` call for an action, through the adapter it was quoted on.
+     */
+    pub fn build_call(
+        &self,
+        token_pair: &TokenPair,
+        action_to_take: &KeeperAction,
+        deadline: U256,
+    ) -> Result<StabilityModuleCall<M>> {
+        if let KeeperAction::None(_) = action_to_take {
+            bail!("There is no call to make when no action is needed");
+        }
+        let swap_details = action_to_take.swap_details();
+        let adapter = self
+            .adapter_registry
+            .get(&swap_details.adapter_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No adapter named {} is registered for {}",
+                    swap_details.adapter_name,
+                    token_pair.symbol
+                )
+            })?;
+        let delegate_call_data =
+            generate_delegate_call_data(adapter, swap_details, token_pair.slippage_bps, deadline)
+                .map_err(|error| anyhow!("Unable to encode swap for {}: {error}", adapter.name()))?;
+        Ok(generate_stability_module_call(
+            &self.stability_module,
+            adapter,
+            action_to_take,
+            swap_details,
+            delegate_call_data,
+        ))
+    }
+
+    /**
+     * Broadcasts the call, privately when a relay is configured, and waits for the required confirmations.  A
+     * call the module refuses for the keeper's account fails with `NotAuthorizedError`.
+     */
+    #[tracing::instrument(name = "submit", skip_all, fields(tx_hash = tracing::field::Empty))]
+    pub async fn execute(&self, call: StabilityModuleCall<M>) -> Result<TransactionReceipt> {
+        debug!(
+            to = ?call.tx.to(),
+            calldata = %call.tx.data().cloned().unwrap_or_default(),
+            "Submitting call"
+        );
+        let pending_tx = if let Some(bundle_relay) = &self.bundle_relay {
+            let poll_interval =
+                time::Duration::from_millis(self.config.delay_between_checks_ms as u64);
+            bundle_relay
+                .submit(&self.provider, call.tx, poll_interval)
+                .instrument(info_span!("send", private = true))
+                .await
+                .map_err(|error| anyhow!("Error during private submission: {error}"))?
+        } else {
+            match call.send().instrument(info_span!("send")).await {
+                Ok(pending_tx) => pending_tx,
+                Err(contract_error) => {
+                    let revert_reason =
+                        contract_error.decode_contract_revert::<AzosStabilityModuleErrors>();
+                    if let Some(
+                        AzosStabilityModuleErrors::NotAuthorized(_)
+                        | AzosStabilityModuleErrors::Unauthorized(_),
+                    ) = revert_reason
+                    {
+                        return Err(NotAuthorizedError.into());
+                    }
+                    bail!("Error during function call: {contract_error}, revert reason: {revert_reason:?}");
+                }
+            }
+        };
+        Span::current().record("tx_hash", tracing::field::debug(pending_tx.tx_hash()));
+        self.wait_for_confirmations(pending_tx).await
+    }
+
+    #[tracing::instrument(skip_all, fields(confirmations = self.config.tx_confirmations_required))]
+    async fn wait_for_confirmations(
+        &self,
+        pending_tx: PendingTransaction<'_, M::Provider>,
+    ) -> Result<TransactionReceipt> {
+        info!(
+            "Awaiting {} confirmations..",
+            self.config.tx_confirmations_required
+        );
+        pending_tx
+            .confirmations(self.config.tx_confirmations_required)
+            .await
+            .map_err(|error| anyhow!("Error during transaction: {error}"))?
+            .ok_or_else(|| anyhow!("Transaction was dropped before confirming"))
+    }
+
+    /**
+     * The keeper account's ETH balance in wei.
+     */
+    pub async fn get_wallet_balance(&self) -> Result<U256> {
+        let address = self.provider.default_sender().unwrap_or_default();
+        self.provider
+            .get_balance(address, None)
+            .await
+            .map_err(|error| anyhow!("Unable to read the wallet balance: {error}"))
+    }
+
+    /**
+     * Alerts when the wallet balance falls below the configured floor, once each time it crosses it.
+     */
+    async fn check_balance_floor(&mut self) -> Result<()> {
+        let Some(floor) = self
+            .config
+            .alerts
+            .as_ref()
+            .and_then(|alerts| alerts.wallet_balance_floor)
+        else {
+            return Ok(());
+        };
+        let balance = self.get_wallet_balance().await?;
+        let below_balance_floor = balance < decimal_to_u256(floor, 18);
+        if below_balance_floor && !self.below_balance_floor {
+            // Below a Decimal floor, so it fits in one too
+            let balance = u256_to_decimal(balance, 18);
+            self.alert(Alert::LowBalance { balance, floor }).await;
+        }
+        self.below_balance_floor = below_balance_floor;
+        Ok(())
+    }
+
+    /**
+     * Evaluates every pair at `block_number` and executes whatever actions they need.  Everything logged meanwhile
+     * carries the tick's id and block.
+     */
+    pub async fn tick(&mut self, block_number: u64) -> Result<()> {
+        self.tick_id += 1;
+        let span = info_span!("tick", tick_id = self.tick_id, block = block_number);
+        self.tick_block(block_number).instrument(span).await
+    }
+
+    /**
+     * Measures the local clock against `block_number` and brings the module view up to it, returning the block's
+     * timestamp.  The health summary is logged at info whenever its state changes.
+     */
+    pub async fn check_health(&mut self, block_number: u64) -> Result<U256> {
+        let block_timestamp = self
+            .provider
+            .get_block(block_number)
+            .await?
+            .ok_or_else(|| anyhow!("Block {block_number} is missing"))?
+            .timestamp;
+        self.health
+            .record_clock_skew(clock_skew_seconds(block_timestamp));
+        self.refresh_module_view(block_number).await?;
+        let state = self.health.state();
+        if self.reported_health != Some(state) {
+            info!("Keeper health: {}", self.health);
+            self.reported_health = Some(state);
+        } else {
+            debug!("Keeper health: {}", self.health);
+        }
+        Ok(block_timestamp)
+    }
+
+    async fn tick_block(&mut self, block_number: u64) -> Result<()> {
+        let block_timestamp = self.check_health(block_number).await?;
+        self.check_balance_floor().await?;
+        if self.module_view.wound_down {
+            error!("The stability module has been wound down, taking no actions");
+            return Ok(());
+        }
+        if !self.authorized {
+            info!("Paused until the keeper's account is authorized on the stability module");
+            return Ok(());
+        }
+        let deadline = get_swap_deadline(block_timestamp, self.config.swap_deadline_seconds);
+
+        for index in 0..self.config.token_pairs.len() {
+            let token_pair = self.config.token_pairs[index].clone();
+            let span = info_span!("pair", pair = %token_pair.symbol);
+            self.tick_pair(&token_pair, block_number, deadline)
+                .instrument(span)
+                .await;
+            // Every other call would revert the same way, and burn gas doing so
+            if !self.authorized {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn tick_pair(&mut self, token_pair: &TokenPair, block_number: u64, deadline: U256) {
+        debug!("Evaluating pair");
+        let quotes = match self.quote_pair(token_pair).await {
+            Ok(quotes) => quotes,
+            Err(error) => {
+                error!("Unable to quote the pair, skipping it this tick: {error}");
+                return;
+            }
+        };
+        let out_of_band_price = quotes
+            .iter()
+            .map(|quote| quote.swap_details().dex_price)
+            .find(|dex_price| {
+                !decimal_is_within_allowed_range(*dex_price, self.config.ratio_range_allowed)
+            });
+        match (out_of_band_price, &self.config.alerts) {
+            (Some(price), Some(alerts)) => {
+                let since = *self
+                    .out_of_band_since
+                    .entry(token_pair.symbol.clone())
+                    .or_insert(block_number);
+                // A reorg can take the chain back before the block it was first seen out of range
+                let blocks = block_number.saturating_sub(since) + 1;
+                if blocks >= alerts.out_of_band_blocks {
+                    self.alert(Alert::OutOfBand {
+                        token_pair: token_pair.symbol.clone(),
+                        price,
+                        blocks,
+                    })
+                    .await;
+                }
+            }
+            _ => {
+                self.out_of_band_since.remove(&token_pair.symbol);
+            }
+        }
+
+        let actions = match self.select_actions(quotes).await {
+            Ok(actions) => actions,
+            Err(error) => {
+                error!("Unable to compare the pair's venues, skipping it this tick: {error}");
+                return;
+            }
+        };
+        for action_to_take in actions {
+            let swap_details = action_to_take.swap_details();
+            info!(
+                "Swapping {} {} for at least {} {}",
+                swap_details.amount_to_sell,
+                swap_details.token_to_sell.symbol,
+                apply_slippage_bps(swap_details.amount_to_buy_min, token_pair.slippage_bps),
+                swap_details.token_to_buy.symbol
+            );
+            if let Err(error) = self.preflight(token_pair, &action_to_take).await {
+                error!("Not swapping on {:?}: {error}", swap_details.venue);
+                continue;
+            }
+            let call = match self.build_call(token_pair, &action_to_take, deadline) {
+                Ok(call) => call,
+                Err(error) => {
+                    error!("{error}");
+                    continue;
+                }
+            };
+
+            // Wallet ethereum balance
+            match self.get_wallet_balance().await {
+                Ok(balance) => info!(balance = %format_ether(balance), "Current wallet balance"),
+                Err(error) => warn!("{error}"),
+            }
+
+            info!("Calling function..");
+            match self.execute(call).await {
+                Ok(receipt) => info!(
+                    tx_hash = ?receipt.transaction_hash,
+                    block = ?receipt.block_number,
+                    "Successful transaction!"
+                ),
+                Err(error) => {
+                    error!("{error}");
+                    self.alert(Alert::TransactionFailed {
+                        token_pair: token_pair.symbol.clone(),
+                        error: error.to_string(),
+                    })
+                    .await;
+                    if error.is::<NotAuthorizedError>() {
+                        self.authorization_required = true;
+                        self.record_authorization(false).await;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Ticks once for every new block, forever.
+     */
+    pub async fn run(&mut self) {
+        let delay_between_checks =
+            time::Duration::from_millis(self.config.delay_between_checks_ms as u64);
+
+        // Track which block we've last seen to only handle blocks once
+        let mut last_block_processed: u64 = 0;
+
+        // Core loop
+        info!("Configuration loaded, initiating keeper loop");
+        loop {
+            let current_block = self.provider.get_block_number().await.unwrap().as_u64();
+            if current_block > last_block_processed {
+                last_block_processed = current_block;
+                info!(
+                    block = current_block,
+                    "Unseen block, ticking keeper process"
+                );
+                if let Err(error) = self.tick(current_block).await {
+                    error!(block = current_block, "Tick failed: {error}");
+                    self.alert(Alert::TickFailed {
+                        block: current_block,
+                        error: error.to_string(),
+                    })
+                    .await;
+                }
+                if self.module_view.wound_down {
+                    error!("Stopping the keeper, the stability module has been wound down");
+                    return;
+                }
+            } else {
+                info!(
+                    block = current_block,
+                    "Skipping this block, as it has already been handled"
+                );
+            }
+
+            info!("Sleeping for {}ms", self.config.delay_between_checks_ms);
+            thread::sleep(delay_between_checks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlertConfig, UniswapV3Config};
+    use crate::contracts::azos_adapter_uniswap_v2::SwapCall;
+    use crate::contracts::azos_stability_module::{
+        AddAuthorizationFilter, AuthorizedAccountsWithAccountCall, ContractAndSellCall,
+        DebtCeilingCall, DebtCeilingChangeFilter, ExpandAndBuyCall, GetAdapterCall, GetDebtCall,
+        WindDownFilter,
+    };
+    use crate::contracts::uniswap_v2_pair::GetReservesReturn;
+    use crate::contracts::uniswap_v2_router02::{GetAmountsInCall, GetAmountsOutCall};
+    use crate::contracts::uniswap_v3_quoter_v2::{
+        QuoteExactInputSingleCall, QuoteExactInputSingleReturn,
+    };
+    use crate::rpc::LOG_CHUNK_BLOCKS;
+    use crate::test_utils::{
+        mock_chain, mock_http_server, mock_http_server_for, request_body, test_config, units,
+        MockChain,
+    };
+    use ethers::abi::{decode, AbiDecode, ParamType, Token as AbiToken};
+    use ethers::utils::format_bytes32_string;
+
+    type MockKeeper = Keeper<Provider<Arc<MockChain>>>;
+
+    const DEADLINE: u64 = 1_700_000_120;
+
+    async fn keeper(config: Config, chain: &Arc<MockChain>) -> MockKeeper {
+        Keeper::with_provider(config, chain.provider())
+            .await
+            .unwrap()
+    }
+
+    fn decode_swap_data(data: &Bytes) -> Vec<AbiToken> {
+        let SwapCall { data } = SwapCall::decode(data).unwrap();
+        decode(
+            &[
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Array(Box::new(ParamType::Address)),
+                ParamType::Uint(256),
+                ParamType::Address,
+            ],
+            &data,
+        )
+        .unwrap()
+    }
+
+    fn path_tokens(path: &[Address]) -> AbiToken {
+        AbiToken::Array(
+            path.iter()
+                .map(|address| AbiToken::Address(*address))
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn balanced_pools_need_no_action() {
+        let config = test_config();
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        let keeper = keeper(config, &chain).await;
+
+        let token_pair = &keeper.config().token_pairs[0];
+        assert!(keeper.evaluate_pair(token_pair).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn expensive_zai_is_sold_by_expanding() {
+        let config = test_config();
+        let (usdc, zai) = (
+            config.token_pairs[0].token_0.address,
+            config.token_pairs[0].token_1.address,
+        );
+        let chain = mock_chain(&config, units("1000000", 18), units("980000", 18), true);
+        // Reaching the 1.001 target takes 9505 USDC out of the pool
+        chain.on_call(
+            config.uniswap_router_address,
+            GetAmountsInCall {
+                amount_out: units("9505", 18),
+                path: vec![zai, usdc],
+            },
+            vec![units("9600", 18), units("9505", 18)],
+        );
+        let keeper = keeper(config, &chain).await;
+        let token_pair = &keeper.config().token_pairs[0];
+
+        let actions = keeper.evaluate_pair(token_pair).await.unwrap();
+        assert_eq!(actions.len(), 1);
+        let KeeperAction::ExpandAndBuy(swap_details) = &actions[0] else {
+            panic!("expected an expansion");
+        };
+        assert_eq!(swap_details.token_to_sell.symbol, "ZAI");
+        assert_eq!(swap_details.amount_to_sell, Decimal::from(9600));
+        assert_eq!(swap_details.token_to_buy.symbol, "USDC");
+        assert_eq!(swap_details.amount_to_buy_min, Decimal::from(9505));
+        assert_eq!(swap_details.path, vec![zai, usdc]);
+
+        let call = keeper
+            .build_call(token_pair, &actions[0], U256::from(DEADLINE))
+            .unwrap();
+        let expand_and_buy = ExpandAndBuyCall::decode(call.tx.data().unwrap()).unwrap();
+        assert_eq!(
+            expand_and_buy.adapter_name,
+            format_bytes32_string("USDC").unwrap()
+        );
+        assert_eq!(expand_and_buy.mint_amount, units("9600", 18));
+        assert_eq!(
+            decode_swap_data(&expand_and_buy.data),
+            vec![
+                AbiToken::Uint(units("9600", 18)),
+                // 50 bps below the quoted 9505
+                AbiToken::Uint(units("9457.475", 18)),
+                path_tokens(&[zai, usdc]),
+                AbiToken::Uint(U256::from(DEADLINE)),
+                AbiToken::Address(keeper.config().uniswap_router_address),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cheap_zai_is_bought_by_contracting() {
+        let config = test_config();
+        let (usdc, zai) = (
+            config.token_pairs[0].token_0.address,
+            config.token_pairs[0].token_1.address,
+        );
+        // ZAI sorts first in the pair, so reserves have to be flipped to read the price
+        let chain = mock_chain(&config, units("980000", 18), units("1000000", 18), false);
+        // Reaching the 0.997 target takes 8515 ZAI out of the pool
+        chain.on_call(
+            config.uniswap_router_address,
+            GetAmountsInCall {
+                amount_out: units("8515", 18),
+                path: vec![usdc, zai],
+            },
+            vec![units("8600", 18), units("8515", 18)],
+        );
+        let keeper = keeper(config, &chain).await;
+        let token_pair = &keeper.config().token_pairs[0];
+
+        let actions = keeper.evaluate_pair(token_pair).await.unwrap();
+        assert_eq!(actions.len(), 1);
+        let KeeperAction::ContractAndSell(swap_details) = &actions[0] else {
+            panic!("expected a contraction");
+        };
+        assert_eq!(swap_details.token_to_sell.symbol, "USDC");
+        assert_eq!(swap_details.amount_to_sell, Decimal::from(8600));
+        assert_eq!(swap_details.amount_to_buy_min, Decimal::from(8515));
+
+        let call = keeper
+            .build_call(token_pair, &actions[0], U256::from(DEADLINE))
+            .unwrap();
+        let contract_and_sell = ContractAndSellCall::decode(call.tx.data().unwrap()).unwrap();
+        assert_eq!(
+            decode_swap_data(&contract_and_sell.data),
+            vec![
+                AbiToken::Uint(units("8600", 18)),
+                AbiToken::Uint(units("8472.425", 18)),
+                path_tokens(&[usdc, zai]),
+                AbiToken::Uint(U256::from(DEADLINE)),
+                AbiToken::Address(keeper.config().uniswap_router_address),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn sizing_follows_each_tokens_decimals() {
+        let mut config = test_config();
+        config.token_pairs[0].token_0.decimals = 6;
+        let (usdc, zai) = (
+            config.token_pairs[0].token_0.address,
+            config.token_pairs[0].token_1.address,
+        );
+        let chain = mock_chain(&config, units("1000000", 6), units("980000", 18), true);
+        chain.on_call(
+            config.uniswap_router_address,
+            GetAmountsInCall {
+                amount_out: units("9505", 6),
+                path: vec![zai, usdc],
+            },
+            vec![units("9600", 18), units("9505", 6)],
+        );
+        let keeper = keeper(config, &chain).await;
+        let token_pair = &keeper.config().token_pairs[0];
+
+        let actions = keeper.evaluate_pair(token_pair).await.unwrap();
+        let swap_details = actions[0].swap_details();
+        assert_eq!(swap_details.amount_to_sell, Decimal::from(9600));
+        assert_eq!(swap_details.amount_to_buy_min, Decimal::from(9505));
+
+        let call = keeper
+            .build_call(token_pair, &actions[0], U256::from(DEADLINE))
+            .unwrap();
+        let expand_and_buy = ExpandAndBuyCall::decode(call.tx.data().unwrap()).unwrap();
+        assert_eq!(
+            decode_swap_data(&expand_and_buy.data)[1],
+            AbiToken::Uint(units("9457.475", 6))
+        );
+    }
+
+    #[tokio::test]
+    async fn fresh_quotes_must_meet_the_slippage_bound() {
+        let config = test_config();
+        let (usdc, zai) = (
+            config.token_pairs[0].token_0.address,
+            config.token_pairs[0].token_1.address,
+        );
+        let chain = mock_chain(&config, units("1000000", 18), units("980000", 18), true);
+        chain.on_call(
+            config.uniswap_router_address,
+            GetAmountsInCall {
+                amount_out: units("9505", 18),
+                path: vec![zai, usdc],
+            },
+            vec![units("9600", 18), units("9505", 18)],
+        );
+        let router_address = config.uniswap_router_address;
+        let fresh_quote = |amount_out: &str| {
+            chain.on_call(
+                router_address,
+                GetAmountsOutCall {
+                    amount_in: units("9600", 18),
+                    path: vec![zai, usdc],
+                },
+                vec![units("9600", 18), units(amount_out, 18)],
+            )
+        };
+        let keeper = keeper(config, &chain).await;
+        let token_pair = &keeper.config().token_pairs[0];
+        let actions = keeper.evaluate_pair(token_pair).await.unwrap();
+
+        // The pool moved against us, below 9505 less 50 bps
+        fresh_quote("9400");
+        assert!(keeper.preflight(token_pair, &actions[0]).await.is_err());
+
+        fresh_quote("9460");
+        assert!(keeper.preflight(token_pair, &actions[0]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn uniswap_v3_swaps_are_quoted_again_before_sending() {
+        let mut config = test_config();
+        let quoter = Address::repeat_byte(0x13);
+        config.uniswap_v3 = Some(UniswapV3Config {
+            factory_address: Address::repeat_byte(0x12),
+            router_address: Address::repeat_byte(0x14),
+            quoter_address: quoter,
+            adapter_name: String::from("USDC_V3"),
+            adapter_address: Address::repeat_byte(0x31),
+            max_tick_boundaries: 100,
+        });
+        let token_pair = config.token_pairs[0].clone();
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        let fresh_quote = |amount_out: &str| {
+            chain.on_any_call::<QuoteExactInputSingleCall>(
+                quoter,
+                QuoteExactInputSingleReturn {
+                    amount_out: units(amount_out, 18),
+                    ..Default::default()
+                },
+            )
+        };
+        let keeper = keeper(config, &chain).await;
+        let swap_details = SwapDetails {
+            dex_price: Decimal::from_str_exact("1.02").unwrap(),
+            venue: Venue::UniswapV3 { fee: 500 },
+            adapter_name: String::from("USDC_V3"),
+            token_to_sell: token_pair.token_1.clone(),
+            amount_to_sell: Decimal::from(9600),
+            token_to_buy: token_pair.token_0.clone(),
+            amount_to_buy_min: Decimal::from(9505),
+            path: vec![token_pair.token_1.address, token_pair.token_0.address],
+        };
+
+        // Nothing to quote with fails closed
+        assert!(keeper.check_fresh_quote(&swap_details, 50).await.is_err());
+
+        fresh_quote("9400");
+        assert!(keeper.check_fresh_quote(&swap_details, 50).await.is_err());
+
+        fresh_quote("9460");
+        assert!(keeper.check_fresh_quote(&swap_details, 50).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn unquotable_swaps_are_skipped() {
+        let config = test_config();
+        let chain = mock_chain(&config, units("1000000", 18), units("980000", 18), true);
+        let keeper = keeper(config, &chain).await;
+
+        let token_pair = &keeper.config().token_pairs[0];
+        assert!(keeper.evaluate_pair(token_pair).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pairs_are_skipped_when_the_quorum_disagrees() {
+        let reserves = |reserve_usdc: &str, reserve_zai: &str| {
+            let reserves = GetReservesReturn {
+                reserve_0: units(reserve_usdc, 18).as_u128(),
+                reserve_1: units(reserve_zai, 18).as_u128(),
+                block_timestamp_last: 0,
+            };
+            serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": Bytes::from(reserves.encode())})
+        };
+        let (first, first_handle) = mock_http_server_for(reserves("1000000", "1000000"), 2).await;
+        let (second, second_handle) = mock_http_server_for(reserves("1000000", "980000"), 2).await;
+        let mut config = test_config();
+        config.rpc_urls = vec![first, second];
+        config.rpc_quorum = Some(2);
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.mine(H256::repeat_byte(0xa0), vec![]);
+        let mut keeper = keeper(config, &chain).await;
+
+        let token_pair = &keeper.config().token_pairs[0];
+        let Err(error) = keeper.evaluate_pair(token_pair).await else {
+            panic!("Reserves the endpoints disagree on were used");
+        };
+        assert!(
+            error.to_string().to_lowercase().contains("quorum"),
+            "{error}"
+        );
+
+        // The tick reads them again, skips the pair and carries on
+        keeper.tick(0).await.unwrap();
+        assert_eq!(first_handle.await.unwrap().len(), 2);
+        assert_eq!(second_handle.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unregistered_adapters_are_refused() {
+        let config = test_config();
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.on_any_call::<GetAdapterCall>(config.stability_module_address, Address::zero());
+
+        let result = Keeper::with_provider(config, chain.provider()).await;
+        assert!(result.is_err_and(|error| error.to_string().contains("not registered")));
+    }
+
+    #[tokio::test]
+    async fn governance_changes_apply_between_ticks() {
+        let config = test_config();
+        let module = config.stability_module_address;
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.mine(H256::repeat_byte(0xa0), vec![]);
+        let mut keeper = keeper(config, &chain).await;
+        keeper.tick(0).await.unwrap();
+
+        let ceiling = chain.mine(
+            H256::repeat_byte(0xa1),
+            vec![Log {
+                address: module,
+                topics: vec![
+                    DebtCeilingChangeFilter::signature(),
+                    H256::from_low_u64_be(500),
+                ],
+                ..Default::default()
+            }],
+        );
+        keeper.tick(ceiling).await.unwrap();
+        assert_eq!(keeper.module_view().debt_ceiling, U256::from(500));
+        assert!(!keeper.module_view().wound_down);
+
+        let wind_down = chain.mine(
+            H256::repeat_byte(0xa2),
+            vec![Log {
+                address: module,
+                topics: vec![
+                    WindDownFilter::signature(),
+                    H256::from(Address::repeat_byte(0x50)),
+                    H256::zero(),
+                    H256::zero(),
+                ],
+                data: U256::zero().encode().into(),
+                ..Default::default()
+            }],
+        );
+        keeper.tick(wind_down).await.unwrap();
+        assert!(keeper.module_view().wound_down);
+        assert!(keeper.health().to_string().starts_with("stopped"));
+    }
+
+    #[tokio::test]
+    async fn governance_changes_are_read_back_in_chunks() {
+        let config = test_config();
+        let module = config.stability_module_address;
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.mine(H256::repeat_byte(0xa0), vec![]);
+        let mut keeper = keeper(config, &chain).await;
+        keeper.tick(0).await.unwrap();
+
+        // The keeper comes back after more blocks than a single eth_getLogs may span
+        for _ in 0..LOG_CHUNK_BLOCKS {
+            chain.mine(H256::repeat_byte(0xa3), vec![]);
+        }
+        let ceiling = chain.mine(
+            H256::repeat_byte(0xa4),
+            vec![Log {
+                address: module,
+                topics: vec![
+                    DebtCeilingChangeFilter::signature(),
+                    H256::from_low_u64_be(500),
+                ],
+                ..Default::default()
+            }],
+        );
+        keeper.tick(ceiling).await.unwrap();
+        assert_eq!(keeper.module_view().debt_ceiling, U256::from(500));
+    }
+
+    #[tokio::test]
+    async fn expansions_stay_under_the_debt_ceiling() {
+        let config = test_config();
+        let chain = mock_chain(&config, units("1000000", 18), units("980000", 18), true);
+        let (usdc, zai) = (
+            config.token_pairs[0].token_0.address,
+            config.token_pairs[0].token_1.address,
+        );
+        chain.on_call(
+            config.uniswap_router_address,
+            GetAmountsInCall {
+                amount_out: units("9505", 18),
+                path: vec![zai, usdc],
+            },
+            vec![units("9600", 18), units("9505", 18)],
+        );
+        chain.on_any_call::<DebtCeilingCall>(config.stability_module_address, units("100000", 18));
+        chain.on_any_call::<GetDebtCall>(
+            config.stability_module_address,
+            I256::from_raw(units("95000", 18)),
+        );
+        let keeper = keeper(config, &chain).await;
+        let token_pair = &keeper.config().token_pairs[0];
+        let actions = keeper.evaluate_pair(token_pair).await.unwrap();
+
+        // Minting 9600 onto 95000 passes the 100000 ceiling
+        let error = keeper.preflight(token_pair, &actions[0]).await.unwrap_err();
+        assert!(error.to_string().contains("debt ceiling"));
+    }
+
+    #[tokio::test]
+    async fn unauthorized_keepers_pause_until_authorized() {
+        let mut config = test_config();
+        config.authorization_required = true;
+        let module = config.stability_module_address;
+        let account = Address::repeat_byte(0x60);
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.on_any_call::<AuthorizedAccountsWithAccountCall>(module, false);
+        chain.mine(H256::repeat_byte(0xb0), vec![]);
+        let provider = Arc::new(Provider::new(chain.clone()).with_sender(account));
+        let mut keeper = Keeper::with_provider(config, provider).await.unwrap();
+        assert!(!keeper.is_authorized());
+        keeper.tick(0).await.unwrap();
+        assert!(keeper
+            .health()
+            .to_string()
+            .starts_with("paused, unauthorized"));
+
+        let add_authorization = |account: Address| Log {
+            address: module,
+            topics: vec![AddAuthorizationFilter::signature()],
+            data: account.encode().into(),
+            ..Default::default()
+        };
+        chain.on_any_call::<AuthorizedAccountsWithAccountCall>(module, true);
+
+        // Another account being authorized is no reason to check again
+        let other = chain.mine(
+            H256::repeat_byte(0xb1),
+            vec![add_authorization(Address::repeat_byte(0x61))],
+        );
+        keeper.tick(other).await.unwrap();
+        assert!(!keeper.is_authorized());
+
+        let granted = chain.mine(H256::repeat_byte(0xb2), vec![add_authorization(account)]);
+        keeper.tick(granted).await.unwrap();
+        assert!(keeper.is_authorized());
+        assert!(!keeper.health().to_string().starts_with("paused"));
+    }
+
+    #[tokio::test]
+    async fn pairs_stuck_out_of_range_are_alerted() {
+        let (webhook, webhook_handle) = mock_http_server(serde_json::json!({})).await;
+        let mut config = test_config();
+        config.alerts = Some(AlertConfig {
+            webhook_urls: vec![webhook],
+            wallet_balance_floor: None,
+            out_of_band_blocks: 2,
+            dedup_seconds: 600,
+            max_per_minute: 10,
+        });
+        // Out of range, but with nothing scripted to size a swap there's no action to take
+        let chain = mock_chain(&config, units("1000000", 18), units("980000", 18), true);
+        chain.mine(H256::repeat_byte(0xc0), vec![]);
+        chain.mine(H256::repeat_byte(0xc1), vec![]);
+        let mut keeper = keeper(config, &chain).await;
+
+        keeper.tick(0).await.unwrap();
+        assert!(!webhook_handle.is_finished());
+        keeper.tick(1).await.unwrap();
+
+        let text = request_body(&webhook_handle.await.unwrap())["text"].to_string();
+        assert!(text.contains("USDC/ZAI has been out of range for 2 blocks"));
+    }
+
+    #[tokio::test]
+    async fn unreadable_balances_fail_the_tick() {
+        let mut config = test_config();
+        config.alerts = Some(AlertConfig {
+            webhook_urls: vec![],
+            wallet_balance_floor: Some(Decimal::ONE),
+            out_of_band_blocks: 2,
+            dedup_seconds: 600,
+            max_per_minute: 10,
+        });
+        // Nothing answers eth_getBalance
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.mine(H256::repeat_byte(0xc5), vec![]);
+        let mut keeper = keeper(config, &chain).await;
+
+        let result = keeper.tick(0).await;
+        assert!(result.is_err_and(|error| error.to_string().contains("wallet balance")));
+    }
+
+    #[tokio::test]
+    async fn out_of_range_pairs_survive_a_reorg_behind_them() {
+        let (webhook, webhook_handle) = mock_http_server(serde_json::json!({})).await;
+        let mut config = test_config();
+        config.alerts = Some(AlertConfig {
+            webhook_urls: vec![webhook],
+            wallet_balance_floor: None,
+            out_of_band_blocks: 2,
+            dedup_seconds: 600,
+            max_per_minute: 10,
+        });
+        let chain = mock_chain(&config, units("1000000", 18), units("980000", 18), true);
+        chain.mine(H256::repeat_byte(0xc2), vec![]);
+        chain.mine(H256::repeat_byte(0xc3), vec![]);
+        let mut keeper = keeper(config, &chain).await;
+
+        keeper.tick(1).await.unwrap();
+        // Block 0 is behind where the pair was first seen out of range, it's counted as the first block again
+        keeper.tick(0).await.unwrap();
+        assert!(!webhook_handle.is_finished());
+        let next = chain.mine(H256::repeat_byte(0xc4), vec![]);
+        keeper.tick(next).await.unwrap();
+
+        let text = request_body(&webhook_handle.await.unwrap())["text"].to_string();
+        assert!(text.contains("USDC/ZAI has been out of range for 2 blocks"));
+    }
+}
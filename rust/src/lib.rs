@@ -0,0 +1,26 @@
+pub mod adapters;
+pub mod admin;
+pub mod backtest;
+pub mod config;
+pub mod contracts;
+pub mod governance;
+pub mod health;
+pub mod indexer;
+pub mod keeper;
+pub mod logging;
+pub mod notifier;
+pub mod relay;
+pub mod routing;
+pub mod rpc;
+pub mod signer;
+pub mod simulate;
+pub mod status;
+pub mod sweep;
+pub mod telemetry;
+#[cfg(test)]
+mod test_utils;
+pub mod types;
+pub mod utils;
+pub mod venues;
+
+pub use keeper::Keeper;
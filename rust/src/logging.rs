@@ -0,0 +1,113 @@
+use crate::telemetry::{self, TraceExport};
+use anyhow::{bail, Result};
+use opentelemetry_sdk::trace::{Tracer, TracerProvider};
+use std::env;
+use tracing::level_filters::LevelFilter;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// How log lines are written, `LOG_FORMAT=json` for pipelines that index them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Result<Self> {
+        match env::var("LOG_FORMAT").unwrap_or_default().as_str() {
+            "" | "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => bail!("LOG_FORMAT must be text or json, not {other}"),
+        }
+    }
+}
+
+/**
+ * A subscriber writing to `writer`, filtered by `filter` with `RUST_LOG`'s syntax.  JSON lines carry the fields of
+ * every span they were emitted in, such as the tick, block, pair and transaction hash.  Spans down to info are sent
+ * to `tracer` whatever the filter logs.
+ */
+pub fn subscriber<W>(
+    format: LogFormat,
+    filter: &str,
+    writer: W,
+    tracer: Option<Tracer>,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let fmt_layer = match format {
+        LogFormat::Text => fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+    };
+    let tracing_layer = tracer.map(|tracer| {
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(LevelFilter::INFO)
+    });
+    Box::new(
+        tracing_subscriber::registry()
+            .with(fmt_layer.with_filter(EnvFilter::new(filter)))
+            .with(tracing_layer),
+    )
+}
+
+/**
+ * Logs to stderr as `LOG_FORMAT` asks, at the levels `RUST_LOG` sets, errors only otherwise.  Records dependencies
+ * write through the `log` crate are forwarded too, so they pick up the same span context.  Spans are exported as
+ * `TraceExport::from_env` reads, through the provider handed back, which has to be kept until exiting.
+ */
+pub fn init() -> Result<Option<TracerProvider>> {
+    let filter = env::var("RUST_LOG").unwrap_or(String::from("error"));
+    let tracer_provider = TraceExport::from_env()?
+        .map(|export| telemetry::tracer_provider(&export))
+        .transpose()?;
+    subscriber(
+        LogFormat::from_env()?,
+        &filter,
+        std::io::stderr,
+        tracer_provider.as_ref().map(telemetry::tracer),
+    )
+    .init();
+    Ok(tracer_provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::Lines;
+    use serde_json::Value;
+    use tracing::{info, info_span};
+
+    #[test]
+    fn json_lines_carry_their_span_context() {
+        let lines = Lines::default();
+        let writer = lines.clone();
+        let subscriber = subscriber(LogFormat::Json, "info", move || writer.clone(), None);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let tick = info_span!("tick", tick_id = 7, block = 26);
+            let _tick = tick.enter();
+            let pair = info_span!("pair", pair = "USDC/ZAI");
+            let _pair = pair.enter();
+            info!(tx_hash = "0xab", "Successful transaction");
+        });
+
+        let output = lines.text();
+        let line: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(line["fields"]["message"], "Successful transaction");
+        assert_eq!(line["fields"]["tx_hash"], "0xab");
+        assert_eq!(line["spans"][0]["tick_id"], 7);
+        assert_eq!(line["spans"][0]["block"], 26);
+        assert_eq!(line["span"]["pair"], "USDC/ZAI");
+    }
+}
@@ -0,0 +1,275 @@
+use crate::config::AlertConfig;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// The window `max_per_minute` is counted over
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Something an operator should hear about without watching the logs
+#[derive(Clone, Debug, PartialEq)]
+pub enum Alert {
+    TransactionFailed {
+        token_pair: String,
+        error: String, // Carries the decoded revert reason
+    },
+    Paused {
+        reason: String,
+    },
+    Resumed {
+        reason: String,
+    },
+    LowBalance {
+        balance: Decimal,
+        floor: Decimal,
+    },
+    RpcFailover {
+        failed: String,
+        next: String,
+        error: String,
+    },
+    OutOfBand {
+        token_pair: String,
+        price: Decimal,
+        blocks: u64,
+    },
+    TickFailed {
+        block: u64,
+        error: String,
+    },
+}
+
+impl Alert {
+    /**
+     * What makes two alerts the same condition, only the first of them is sent within the dedup window.
+     */
+    fn key(&self) -> String {
+        match self {
+            Alert::TransactionFailed { token_pair, error } => {
+                format!("transaction_failed:{token_pair}:{error}")
+            }
+            Alert::Paused { reason } => format!("paused:{reason}"),
+            Alert::Resumed { reason } => format!("resumed:{reason}"),
+            Alert::LowBalance { .. } => String::from("low_balance"),
+            Alert::RpcFailover { failed, .. } => format!("rpc_failover:{failed}"),
+            Alert::OutOfBand { token_pair, .. } => format!("out_of_band:{token_pair}"),
+            Alert::TickFailed { error, .. } => format!("tick_failed:{error}"),
+        }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Alert::TransactionFailed { token_pair, error } => {
+                write!(f, "Transaction for {token_pair} failed: {error}")
+            }
+            Alert::Paused { reason } => write!(f, "Keeper paused: {reason}"),
+            Alert::Resumed { reason } => write!(f, "Keeper resumed: {reason}"),
+            Alert::LowBalance { balance, floor } => {
+                write!(
+                    f,
+                    "Wallet balance of {balance} ETH is below the floor of {floor} ETH"
+                )
+            }
+            Alert::RpcFailover {
+                failed,
+                next,
+                error,
+            } => write!(f, "RPC failover from {failed} to {next}: {error}"),
+            Alert::OutOfBand {
+                token_pair,
+                price,
+                blocks,
+            } => write!(
+                f,
+                "{token_pair} has been out of range for {blocks} blocks, price {price}"
+            ),
+            Alert::TickFailed { block, error } => {
+                write!(f, "Tick at block {block} failed: {error}")
+            }
+        }
+    }
+}
+
+/// The payload shape a webhook expects
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WebhookKind {
+    Slack,
+    Discord,
+}
+
+impl WebhookKind {
+    /**
+     * Discord serves its webhooks under `/api/webhooks/`, anything else is given Slack's payload, which Mattermost
+     * and most chat bridges accept too.
+     */
+    pub fn of(url: &str) -> Self {
+        if url.contains("/api/webhooks/") {
+            WebhookKind::Discord
+        } else {
+            WebhookKind::Slack
+        }
+    }
+
+    pub fn payload(&self, text: &str) -> Value {
+        match self {
+            WebhookKind::Slack => json!({ "text": text }),
+            WebhookKind::Discord => json!({ "content": text }),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SentAlerts {
+    by_key: HashMap<String, Instant>,
+    recent: VecDeque<Instant>, // Within the last `RATE_WINDOW`, oldest first
+}
+
+/// Posts alerts to the configured webhooks, holding back repeats and anything past the rate limit
+#[derive(Debug)]
+pub struct Notifier {
+    webhook_urls: Vec<String>,
+    dedup_window: Duration,
+    max_per_minute: usize,
+    client: reqwest::Client,
+    sent: Mutex<SentAlerts>,
+}
+
+impl Notifier {
+    pub fn new(config: &AlertConfig) -> Self {
+        Notifier {
+            webhook_urls: config.webhook_urls.clone(),
+            dedup_window: Duration::from_secs(config.dedup_seconds),
+            max_per_minute: config.max_per_minute,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .unwrap(),
+            sent: Mutex::new(SentAlerts::default()),
+        }
+    }
+
+    /**
+     * Whether `alert` may go out at `now`, recording it as sent when it may.
+     */
+    fn admit(&self, alert: &Alert, now: Instant) -> bool {
+        let mut sent = self.sent.lock().unwrap();
+        let dedup_window = self.dedup_window;
+        sent.by_key
+            .retain(|_, sent_at| now.duration_since(*sent_at) < dedup_window);
+        while let Some(oldest) = sent.recent.front() {
+            if now.duration_since(*oldest) < RATE_WINDOW {
+                break;
+            }
+            sent.recent.pop_front();
+        }
+
+        let key = alert.key();
+        if sent.by_key.contains_key(&key) {
+            debug!(%alert, "Holding back a repeated alert");
+            return false;
+        }
+        if sent.recent.len() >= self.max_per_minute {
+            warn!(%alert, "Alert rate limit reached, dropping it");
+            return false;
+        }
+        sent.by_key.insert(key, now);
+        sent.recent.push_back(now);
+        true
+    }
+
+    /**
+     * Posts `alert` to every webhook.  Failing to deliver it is only logged, alerting must never stop the keeper.
+     */
+    pub async fn notify(&self, alert: Alert) {
+        if !self.admit(&alert, Instant::now()) {
+            return;
+        }
+        let text = format!("azos-keeper: {alert}");
+        // Webhook URLs embed their credentials, so they're never logged
+        for (index, url) in self.webhook_urls.iter().enumerate() {
+            let result = self
+                .client
+                .post(url)
+                .json(&WebhookKind::of(url).payload(&text))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(error) = result {
+                warn!(
+                    webhook = index,
+                    error = %error.without_url(),
+                    "Unable to post alert"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{mock_http_server, request_body};
+
+    fn notifier(webhook_urls: Vec<String>, max_per_minute: usize) -> Notifier {
+        Notifier::new(&AlertConfig {
+            webhook_urls,
+            wallet_balance_floor: None,
+            out_of_band_blocks: 10,
+            dedup_seconds: 600,
+            max_per_minute,
+        })
+    }
+
+    #[tokio::test]
+    async fn alerts_are_posted_in_each_webhooks_shape() {
+        let (slack, slack_handle) = mock_http_server(json!({})).await;
+        let (discord, discord_handle) = mock_http_server(json!({})).await;
+        let notifier = notifier(vec![slack, format!("{discord}/api/webhooks/1/token")], 10);
+
+        notifier
+            .notify(Alert::Paused {
+                reason: String::from("module wound down"),
+            })
+            .await;
+
+        let text = "azos-keeper: Keeper paused: module wound down";
+        let slack_request = slack_handle.await.unwrap();
+        assert!(slack_request.starts_with("POST / "));
+        assert_eq!(request_body(&slack_request), json!({ "text": text }));
+        let discord_request = discord_handle.await.unwrap();
+        assert!(discord_request.starts_with("POST /api/webhooks/1/token "));
+        assert_eq!(request_body(&discord_request), json!({ "content": text }));
+    }
+
+    #[test]
+    fn repeats_and_bursts_are_held_back() {
+        let notifier = notifier(vec![], 2);
+        let now = Instant::now();
+        let failover = |failed: &str| Alert::RpcFailover {
+            failed: String::from(failed),
+            next: String::from("http://b"),
+            error: String::from("connection refused"),
+        };
+
+        assert!(notifier.admit(&failover("http://a"), now));
+        // The same endpoint failing again is the same alert
+        assert!(!notifier.admit(&failover("http://a"), now + Duration::from_secs(1)));
+        assert!(notifier.admit(&failover("http://c"), now + Duration::from_secs(2)));
+        // Two a minute at most
+        let low_balance = Alert::LowBalance {
+            balance: Decimal::ONE,
+            floor: Decimal::TWO,
+        };
+        assert!(!notifier.admit(&low_balance, now + Duration::from_secs(3)));
+        assert!(notifier.admit(&low_balance, now + Duration::from_secs(61)));
+
+        // Once the dedup window has passed, a condition that persists is alerted again
+        assert!(notifier.admit(&failover("http://a"), now + Duration::from_secs(600)));
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{bail, Result};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Sends signed transactions privately to a relay speaking `eth_sendBundle`, keeping them out of the public mempool
+pub struct BundleRelay {
+    url: String,
+    max_blocks: u64,
+    client: reqwest::Client,
+}
+
+impl BundleRelay {
+    pub fn new(url: String, max_blocks: u64) -> Self {
+        BundleRelay {
+            url,
+            max_blocks,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn bundle_request(raw_tx: &Bytes, block_number: u64) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendBundle",
+            "params": [{
+                "txs": [raw_tx],
+                "blockNumber": format!("{block_number:#x}"),
+            }],
+        })
+    }
+
+    /**
+     * Asks the relay to include `raw_tx` in `block_number`.  Requests are signed by the keeper, as Flashbots-style
+     * relays use the signature to identify searchers.
+     */
+    pub async fn send_bundle<M: Middleware>(
+        &self,
+        provider: &M,
+        from: Address,
+        raw_tx: &Bytes,
+        block_number: u64,
+    ) -> Result<()> {
+        let body = Self::bundle_request(raw_tx, block_number).to_string();
+        let body_hash = H256::from(keccak256(body.as_bytes()));
+        let signature = provider
+            .sign(format!("{body_hash:?}").into_bytes(), &from)
+            .await
+            .map_err(|error| anyhow::anyhow!("Unable to sign bundle: {error}"))?;
+
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", format!("{from:?}:0x{signature}"))
+            .body(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            bail!("Relay rejected bundle for block {block_number}: {error}");
+        }
+        debug!(block = block_number, %response, "Bundle accepted");
+        Ok(())
+    }
+
+    /**
+     * Signs `tx` and bundles it for each of the next `max_blocks` blocks.  If it hasn't landed by then it's sent to the
+     * public mempool instead.
+     */
+    pub async fn submit<'a, M: Middleware + 'static>(
+        &self,
+        provider: &'a Arc<M>,
+        mut tx: TypedTransaction,
+        poll_interval: Duration,
+    ) -> Result<PendingTransaction<'a, M::Provider>> {
+        let from = provider
+            .default_sender()
+            .ok_or_else(|| anyhow::anyhow!("The provider has no account to sign with"))?;
+        tx.set_from(from);
+        provider.fill_transaction(&mut tx, None).await?;
+        let signature = provider.sign_transaction(&tx, from).await?;
+        let raw_tx = tx.rlp_signed(&signature);
+        let tx_hash = H256::from(keccak256(&raw_tx));
+
+        let current_block = provider.get_block_number().await?.as_u64();
+        let last_target_block = current_block + self.max_blocks;
+        let mut bundles_sent = 0;
+        for block_number in current_block + 1..=last_target_block {
+            match self
+                .send_bundle(provider.as_ref(), from, &raw_tx, block_number)
+                .await
+            {
+                Ok(()) => bundles_sent += 1,
+                Err(error) => warn!(block = block_number, "{error}"),
+            }
+        }
+        info!(
+            ?tx_hash,
+            bundles_sent, last_target_block, "Sent the transaction to the relay"
+        );
+
+        // Nothing to wait for if the relay turned every bundle away
+        if bundles_sent > 0 {
+            loop {
+                if provider.get_transaction_receipt(tx_hash).await?.is_some() {
+                    info!(?tx_hash, "Bundle landed");
+                    return Ok(PendingTransaction::new(tx_hash, provider.provider()));
+                }
+                if provider.get_block_number().await?.as_u64() > last_target_block {
+                    break;
+                }
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+
+        warn!(
+            last_target_block,
+            "Bundle not included, falling back to the public mempool"
+        );
+        Ok(provider.provider().send_raw_transaction(raw_tx).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{mock_http_server, mock_http_server_for, request_body};
+
+    fn wallet() -> LocalWallet {
+        "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap()
+    }
+
+    // Bundles are signed locally, the mocked chain is never asked anything
+    fn signing_provider() -> SignerMiddleware<Provider<MockProvider>, LocalWallet> {
+        let (provider, _mock) = Provider::mocked();
+        SignerMiddleware::new(provider, wallet())
+    }
+
+    // Fully filled in, so submitting asks the chain only for blocks, receipts and the public fallback
+    fn filled_tx() -> TypedTransaction {
+        TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .value(1)
+            .gas(21_000)
+            .gas_price(1_000_000_000)
+            .nonce(7)
+            .chain_id(1)
+            .into()
+    }
+
+    fn relay_accepting() -> Value {
+        json!({"jsonrpc": "2.0", "id": 1, "result": {"bundleHash": "0x01"}})
+    }
+
+    #[tokio::test]
+    async fn bundles_are_signed_and_target_a_block() {
+        let (url, handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 1, "result": {"bundleHash": "0x01"}}))
+                .await;
+        let relay = BundleRelay::new(url, 3);
+        let raw_tx = Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]);
+
+        relay
+            .send_bundle(&signing_provider(), wallet().address(), &raw_tx, 26)
+            .await
+            .unwrap();
+
+        let request = handle.await.unwrap();
+        let body = request_body(&request);
+        assert_eq!(body["method"], "eth_sendBundle");
+        assert_eq!(body["params"][0]["txs"][0], "0xdeadbeef");
+        assert_eq!(body["params"][0]["blockNumber"], "0x1a");
+        let signature_header = format!("x-flashbots-signature: {:?}:0x", wallet().address());
+        assert!(request.to_lowercase().contains(&signature_header));
+    }
+
+    #[tokio::test]
+    async fn relay_errors_are_reported() {
+        let (url, _handle) = mock_http_server(
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32000, "message": "bundle too late"}}),
+        )
+        .await;
+        let relay = BundleRelay::new(url, 3);
+
+        let result = relay
+            .send_bundle(
+                &signing_provider(),
+                wallet().address(),
+                &Bytes::from(vec![0x01]),
+                1,
+            )
+            .await;
+        assert!(result.unwrap_err().to_string().contains("bundle too late"));
+    }
+
+    #[tokio::test]
+    async fn bundles_included_by_the_relay_are_returned() {
+        let (url, handle) = mock_http_server(relay_accepting()).await;
+        let relay = BundleRelay::new(url, 1);
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(SignerMiddleware::new(provider, wallet()));
+        // Responses are served last pushed first
+        let receipt = TransactionReceipt {
+            block_number: Some(27.into()),
+            ..Default::default()
+        };
+        mock.push(receipt).unwrap();
+        mock.push(U64::from(26)).unwrap();
+
+        let pending = relay
+            .submit(&provider, filled_tx(), Duration::ZERO)
+            .await
+            .unwrap();
+
+        let body = request_body(&handle.await.unwrap());
+        assert_eq!(body["params"][0]["blockNumber"], "0x1b");
+        let raw_tx: Bytes = serde_json::from_value(body["params"][0]["txs"][0].clone()).unwrap();
+        let tx_hash = H256::from(keccak256(&raw_tx));
+        assert_eq!(pending.tx_hash(), tx_hash);
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getTransactionReceipt", [tx_hash])
+            .unwrap();
+        assert!(mock.assert_request("eth_sendRawTransaction", ()).is_err());
+    }
+
+    #[tokio::test]
+    async fn bundles_not_included_fall_back_to_the_mempool() {
+        let (url, handle) = mock_http_server_for(relay_accepting(), 2).await;
+        let relay = BundleRelay::new(url, 2);
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(SignerMiddleware::new(provider, wallet()));
+        // Responses are served last pushed first
+        mock.push(H256::repeat_byte(0xaa)).unwrap();
+        mock.push(U64::from(29)).unwrap();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push(U64::from(27)).unwrap();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push(U64::from(26)).unwrap();
+
+        relay
+            .submit(&provider, filled_tx(), Duration::ZERO)
+            .await
+            .unwrap();
+
+        // One bundle per block up to max_blocks ahead, all carrying the same transaction
+        let bodies: Vec<Value> = handle
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| request_body(r))
+            .collect();
+        assert_eq!(bodies[0]["params"][0]["blockNumber"], "0x1b");
+        assert_eq!(bodies[1]["params"][0]["blockNumber"], "0x1c");
+        assert_eq!(bodies[0]["params"][0]["txs"], bodies[1]["params"][0]["txs"]);
+        let raw_tx: Bytes =
+            serde_json::from_value(bodies[0]["params"][0]["txs"][0].clone()).unwrap();
+        let tx_hash = H256::from(keccak256(&raw_tx));
+
+        // Polled until past the last targeted block, then sent publicly
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getTransactionReceipt", [tx_hash])
+            .unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getTransactionReceipt", [tx_hash])
+            .unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_sendRawTransaction", [raw_tx])
+            .unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+use crate::config::Config;
+use crate::contracts::uniswap_v2_pair::UniswapV2Pair;
+use crate::rpc::QuorumReader;
+use crate::types::provider::UniswapFactory;
+use crate::types::venue::Venue;
+use crate::utils::constant_product::Hop;
+use anyhow::Result;
+use ethers::abi::Address;
+use ethers::providers::Middleware;
+use ethers::types::U256;
+use ethers::utils::format_units;
+use rust_decimal::{Decimal, MathematicalOps};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+// Reserves are scaled down by this many decimals to fit in a Decimal, it cancels out in every ratio
+const RESERVE_SCALE_DECIMALS: u32 = 18;
+
+fn pair_key(token_a: Address, token_b: Address) -> (Address, Address) {
+    if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    }
+}
+
+/// The Uniswap V2 pairs the keeper knows about, used to find routes between two tokens
+#[derive(Default)]
+pub struct RouteFinder {
+    pairs: HashMap<(Address, Address), Address>,
+    neighbours: HashMap<Address, HashSet<Address>>,
+    quorum_reader: Option<Arc<QuorumReader>>, // Reserves are read through it when configured
+}
+
+impl RouteFinder {
+    /**
+     * Builds the pair graph from the direct pairs, the configured routing tokens, and optionally the first
+     * `route_discovery_max_pairs` entries of the factory's `allPairs`.
+     */
+    pub async fn build<M: Middleware + 'static>(
+        config: &Config,
+        provider: &Arc<M>,
+        uniswap_factory: &UniswapFactory<M>,
+        quorum_reader: Option<Arc<QuorumReader>>,
+    ) -> Result<Self> {
+        let mut route_finder = RouteFinder {
+            quorum_reader,
+            ..Default::default()
+        };
+
+        let endpoints: Vec<(Address, Address)> = config
+            .token_pairs
+            .iter()
+            .filter(|token_pair| {
+                token_pair
+                    .venues
+                    .iter()
+                    .any(|pair_venue| matches!(pair_venue.venue, Venue::UniswapV2))
+            })
+            .map(|token_pair| (token_pair.token_0.address, token_pair.token_1.address))
+            .collect();
+
+        for (token_0, token_1) in &endpoints {
+            let mut candidates = vec![(*token_0, *token_1)];
+            for routing_token in &config.routing_tokens {
+                candidates.push((*token_0, *routing_token));
+                candidates.push((*routing_token, *token_1));
+            }
+            for (token_a, token_b) in candidates {
+                let pair_address = uniswap_factory.get_pair(token_a, token_b).call().await?;
+                if pair_address != Address::zero() {
+                    route_finder.add_pair(token_a, token_b, pair_address);
+                }
+            }
+        }
+
+        if config.route_discovery_max_pairs > 0 {
+            let all_pairs_length = uniswap_factory.all_pairs_length().call().await?;
+            let pairs_to_scan = all_pairs_length.min(U256::from(config.route_discovery_max_pairs));
+            info!(
+                %pairs_to_scan,
+                %all_pairs_length,
+                "Discovering routes from the factory's pairs"
+            );
+            for index in 0..pairs_to_scan.as_usize() {
+                let pair_address = uniswap_factory.all_pairs(U256::from(index)).call().await?;
+                let pair = UniswapV2Pair::new(pair_address, provider.clone());
+                let token_a = pair.token_0().call().await?;
+                let token_b = pair.token_1().call().await?;
+                route_finder.add_pair(token_a, token_b, pair_address);
+            }
+        }
+
+        info!(pairs = route_finder.pairs.len(), "Route finder ready");
+        Ok(route_finder)
+    }
+
+    pub fn add_pair(&mut self, token_a: Address, token_b: Address, pair_address: Address) {
+        self.pairs.insert(pair_key(token_a, token_b), pair_address);
+        self.neighbours.entry(token_a).or_default().insert(token_b);
+        self.neighbours.entry(token_b).or_default().insert(token_a);
+    }
+
+    pub fn pair_address(&self, token_a: Address, token_b: Address) -> Option<Address> {
+        self.pairs.get(&pair_key(token_a, token_b)).copied()
+    }
+
+    /**
+     * Every path from `from` to `to` with at most one intermediate token, the direct path first when it exists.
+     */
+    pub fn candidate_paths(&self, from: Address, to: Address) -> Vec<Vec<Address>> {
+        let mut paths = vec![];
+        if self.pair_address(from, to).is_some() {
+            paths.push(vec![from, to]);
+        }
+        let mut intermediates: Vec<&Address> = self
+            .neighbours
+            .get(&from)
+            .map(|neighbours| neighbours.iter().collect())
+            .unwrap_or_default();
+        intermediates.sort();
+        for intermediate in intermediates {
+            if *intermediate != to && self.pair_address(*intermediate, to).is_some() {
+                paths.push(vec![from, *intermediate, to]);
+            }
+        }
+        paths
+    }
+
+    /**
+     * Reserves of each hop along `path`, oriented in the direction of travel and scaled to fit a Decimal.
+     */
+    pub async fn get_hops<M: Middleware + 'static>(
+        &self,
+        provider: &Arc<M>,
+        path: &[Address],
+    ) -> Result<Vec<Hop>> {
+        let mut hops = vec![];
+        for tokens in path.windows(2) {
+            let pair_address = self.pair_address(tokens[0], tokens[1]).ok_or_else(|| {
+                anyhow::anyhow!("No pair between {:?} and {:?}", tokens[0], tokens[1])
+            })?;
+            let (reserve_0, reserve_1, pair_token_0) = match &self.quorum_reader {
+                Some(quorum_reader) => read_pair(quorum_reader.clone(), pair_address).await?,
+                None => read_pair(provider.clone(), pair_address).await?,
+            };
+            let (reserve_in, reserve_out) = if pair_token_0 == tokens[0] {
+                (reserve_0, reserve_1)
+            } else {
+                (reserve_1, reserve_0)
+            };
+            debug!(pair = ?pair_address, %reserve_in, %reserve_out, "Hop reserves");
+            hops.push(Hop {
+                reserve_in: scale_reserve(reserve_in),
+                reserve_out: scale_reserve(reserve_out),
+            });
+        }
+        Ok(hops)
+    }
+}
+
+async fn read_pair<M: Middleware + 'static>(
+    provider: Arc<M>,
+    pair_address: Address,
+) -> Result<(u128, u128, Address)> {
+    let pair = UniswapV2Pair::new(pair_address, provider);
+    let (reserve_0, reserve_1, _timestamp) = pair.get_reserves().call().await?;
+    let pair_token_0 = pair.token_0().call().await?;
+    Ok((reserve_0, reserve_1, pair_token_0))
+}
+
+fn scale_reserve(reserve: u128) -> Decimal {
+    Decimal::from_str(&format_units(U256::from(reserve), RESERVE_SCALE_DECIMALS).unwrap()).unwrap()
+}
+
+/**
+ * Converts an amount in the scaled units of `get_hops` into a human readable token amount.
+ */
+pub fn scaled_to_token_amount(amount: Decimal, token_decimals: u64) -> Decimal {
+    let scale = RESERVE_SCALE_DECIMALS as u64;
+    if token_decimals <= scale {
+        amount * Decimal::TEN.powu(scale - token_decimals)
+    } else {
+        amount / Decimal::TEN.powu(token_decimals - scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direct_path_comes_before_routed_paths() {
+        let (usdc, zai, weth, dai) = (
+            Address::repeat_byte(1),
+            Address::repeat_byte(2),
+            Address::repeat_byte(3),
+            Address::repeat_byte(4),
+        );
+        let mut route_finder = RouteFinder::default();
+        route_finder.add_pair(zai, weth, Address::repeat_byte(10));
+        route_finder.add_pair(weth, usdc, Address::repeat_byte(11));
+        route_finder.add_pair(zai, dai, Address::repeat_byte(12));
+        assert_eq!(
+            route_finder.candidate_paths(zai, usdc),
+            vec![vec![zai, weth, usdc]]
+        );
+
+        route_finder.add_pair(usdc, zai, Address::repeat_byte(13));
+        assert_eq!(
+            route_finder.candidate_paths(zai, usdc),
+            vec![vec![zai, usdc], vec![zai, weth, usdc]]
+        );
+    }
+
+    #[test]
+    fn scaled_amounts_convert_back_to_token_amounts() {
+        let six_decimals = scaled_to_token_amount(Decimal::ONE, 6);
+        assert_eq!(six_decimals, Decimal::from(1_000_000_000_000u64));
+        assert_eq!(scaled_to_token_amount(Decimal::ONE, 18), Decimal::ONE);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::notifier::{Alert, Notifier};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use ethers::providers::{
+    Http, HttpClientError, JsonRpcClient, Provider, ProviderError, Quorum, QuorumProvider,
+    WeightedProvider,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use tracing::{warn, Span};
+
+// Successes slowly earn back trust, failures lose it quickly
+const MAX_SCORE: i64 = 10;
+const FAILURE_PENALTY: i64 = 5;
+
+// Most providers cap how many blocks a single `eth_getLogs` may span
+pub(crate) const LOG_CHUNK_BLOCKS: u64 = 2_000;
+
+/// Reads that must be agreed on by several endpoints before the keeper acts on them
+pub type QuorumReader = Provider<QuorumClient>;
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    client: Http,
+    score: AtomicI64,
+}
+
+/// Sends each request to the healthiest endpoint, moving on to the next when one can't be reached
+#[derive(Debug)]
+pub struct FailoverClient {
+    endpoints: Vec<Endpoint>,
+    notifier: Option<Arc<Notifier>>,
+}
+
+impl FailoverClient {
+    pub fn new(urls: &[String]) -> Result<Self> {
+        if urls.is_empty() {
+            bail!("At least one RPC endpoint is required");
+        }
+        let mut endpoints = vec![];
+        for url in urls {
+            endpoints.push(Endpoint {
+                url: url.clone(),
+                client: Http::from_str(url)?,
+                score: AtomicI64::new(0),
+            });
+        }
+        Ok(FailoverClient {
+            endpoints,
+            notifier: None,
+        })
+    }
+
+    /**
+     * Alerts through `notifier` whenever a request fails over.
+     */
+    pub fn with_notifier(mut self, notifier: Arc<Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /**
+     * Endpoint indexes from the highest score down, configured order breaks ties.
+     */
+    fn endpoint_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+        order.sort_by_key(|index| -self.endpoints[*index].score.load(Ordering::Relaxed));
+        order
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverClient {
+    type Error = HttpClientError;
+
+    #[tracing::instrument(name = "rpc", skip(self, params), fields(endpoint))]
+    async fn request<T: Serialize + Send + Sync + Debug, R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, Self::Error> {
+        let order = self.endpoint_order();
+        let mut last_error = None;
+        for (attempt, index) in order.iter().enumerate() {
+            let endpoint = &self.endpoints[*index];
+            match endpoint.client.request(method, &params).await {
+                Ok(result) => {
+                    Span::current().record("endpoint", endpoint.url.as_str());
+                    let _ = endpoint.score.fetch_update(
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                        |score| Some((score + 1).min(MAX_SCORE)),
+                    );
+                    return Ok(result);
+                }
+                // The node answered, so it's up, the request itself was refused (e.g. a revert)
+                Err(error @ HttpClientError::JsonRpcError(_)) => return Err(error),
+                Err(error) => {
+                    endpoint.score.fetch_sub(FAILURE_PENALTY, Ordering::Relaxed);
+                    if let Some(next) = order.get(attempt + 1) {
+                        let next_url = &self.endpoints[*next].url;
+                        warn!(
+                            failed = %endpoint.url,
+                            next = %next_url,
+                            method,
+                            %error,
+                            "RPC failover"
+                        );
+                        if let Some(notifier) = &self.notifier {
+                            notifier
+                                .notify(Alert::RpcFailover {
+                                    failed: endpoint.url.clone(),
+                                    next: next_url.clone(),
+                                    error: error.to_string(),
+                                })
+                                .await;
+                        }
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.unwrap())
+    }
+}
+
+/// Asks every endpoint, answering once `quorum` of them agree
+#[derive(Debug)]
+pub struct QuorumClient {
+    provider: QuorumProvider<Http>,
+    quorum: usize,
+}
+
+#[async_trait]
+impl JsonRpcClient for QuorumClient {
+    type Error = ProviderError;
+
+    #[tracing::instrument(name = "rpc", skip(self, params), fields(endpoint = "quorum", quorum = self.quorum))]
+    async fn request<T: Serialize + Send + Sync + Debug, R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, Self::Error> {
+        self.provider.request(method, params).await
+    }
+}
+
+/**
+ * A provider whose answers only count once `quorum` of the endpoints return the same thing.
+ */
+pub fn quorum_reader(urls: &[String], quorum: usize) -> Result<Arc<QuorumReader>> {
+    if quorum > urls.len() {
+        bail!(
+            "A quorum of {quorum} needs at least as many RPC endpoints, only {} configured",
+            urls.len()
+        );
+    }
+    let mut providers = vec![];
+    for url in urls {
+        providers.push(WeightedProvider::new(Http::from_str(url)?));
+    }
+    let provider = QuorumProvider::new(Quorum::ProviderCount(quorum), providers);
+    Ok(Arc::new(Provider::new(QuorumClient { provider, quorum })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AlertConfig;
+    use crate::test_utils::{mock_http_server, request_body};
+    use ethers::types::U64;
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    async fn unreachable_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoints_fail_over_and_lose_priority() {
+        let down = unreachable_url().await;
+        let (up, _handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 0, "result": "0x1a"})).await;
+        let client = FailoverClient::new(&[down, up]).unwrap();
+        assert_eq!(client.endpoint_order(), vec![0, 1]);
+
+        let block_number: U64 = client.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block_number, U64::from(26));
+        assert_eq!(client.endpoint_order(), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn failovers_are_alerted() {
+        let down = unreachable_url().await;
+        let (up, _handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 0, "result": "0x1a"})).await;
+        let (webhook, webhook_handle) = mock_http_server(json!({})).await;
+        let notifier = Notifier::new(&AlertConfig {
+            webhook_urls: vec![webhook],
+            wallet_balance_floor: None,
+            out_of_band_blocks: 10,
+            dedup_seconds: 600,
+            max_per_minute: 10,
+        });
+        let client = FailoverClient::new(&[down.clone(), up.clone()])
+            .unwrap()
+            .with_notifier(Arc::new(notifier));
+
+        let _: U64 = client.request("eth_blockNumber", ()).await.unwrap();
+        let text = request_body(&webhook_handle.await.unwrap())["text"].to_string();
+        assert!(text.contains(&format!("RPC failover from {down} to {up}")));
+    }
+
+    #[tokio::test]
+    async fn json_rpc_errors_are_not_failed_over() {
+        let (up, _handle) = mock_http_server(
+            json!({"jsonrpc": "2.0", "id": 0, "error": {"code": 3, "message": "execution reverted"}}),
+        )
+        .await;
+        let down = unreachable_url().await;
+        let client = FailoverClient::new(&[up, down]).unwrap();
+
+        let result: Result<U64, _> = client.request("eth_call", ()).await;
+        assert!(matches!(result, Err(HttpClientError::JsonRpcError(_))));
+        assert_eq!(client.endpoint_order(), vec![0, 1]);
+    }
+
+    #[test]
+    fn quorum_cannot_exceed_the_endpoints() {
+        let urls = vec![String::from("http://localhost:8545")];
+        assert!(quorum_reader(&urls, 2).is_err());
+        assert!(quorum_reader(&urls, 1).is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+use crate::config::SignerConfig;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::utils::rlp::Rlp;
+use serde_json::{json, Value};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum KeeperSignerError {
+    Wallet(WalletError),
+    Remote(String),
+}
+
+impl fmt::Display for KeeperSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeeperSignerError::Wallet(error) => write!(f, "wallet error: {error}"),
+            KeeperSignerError::Remote(error) => write!(f, "remote signer error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for KeeperSignerError {}
+
+impl From<WalletError> for KeeperSignerError {
+    fn from(error: WalletError) -> Self {
+        KeeperSignerError::Wallet(error)
+    }
+}
+
+/// A signer reached over JSON-RPC, such as Web3Signer, so the key never touches the keeper host
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    url: String,
+    address: Address,
+    chain_id: u64,
+    client: reqwest::Client,
+}
+
+impl RemoteSigner {
+    pub fn new(url: String, address: Address) -> Self {
+        RemoteSigner {
+            url,
+            address,
+            chain_id: 1,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value, KeeperSignerError> {
+        let remote_error = |error: reqwest::Error| KeeperSignerError::Remote(error.to_string());
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .send()
+            .await
+            .map_err(remote_error)?
+            .json()
+            .await
+            .map_err(remote_error)?;
+        if let Some(error) = response.get("error") {
+            return Err(KeeperSignerError::Remote(format!(
+                "{method} failed: {error}"
+            )));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| KeeperSignerError::Remote(format!("{method} returned no result")))
+    }
+}
+
+fn parse_hex_bytes(value: &Value) -> Result<Bytes, KeeperSignerError> {
+    value
+        .as_str()
+        .and_then(|hex| Bytes::from_str(hex).ok())
+        .ok_or_else(|| KeeperSignerError::Remote(format!("expected hex bytes, got {value}")))
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    type Error = KeeperSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        let message = Bytes::from(message.as_ref().to_vec());
+        let result = self
+            .request("eth_sign", json!([self.address, message]))
+            .await?;
+        Signature::try_from(parse_hex_bytes(&result)?.as_ref())
+            .map_err(|error| KeeperSignerError::Remote(error.to_string()))
+    }
+
+    /**
+     * `eth_signTransaction` answers with the signed raw transaction (Clef wraps it in `{ raw }`), the signature is
+     * decoded back out of it and checked against our address.
+     */
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        tx.set_chain_id(self.chain_id);
+        let result = self.request("eth_signTransaction", json!([tx])).await?;
+        let raw_tx = parse_hex_bytes(result.get("raw").unwrap_or(&result))?;
+
+        let (_, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw_tx))
+            .map_err(|error| KeeperSignerError::Remote(error.to_string()))?;
+        let signer = signature
+            .recover(tx.sighash())
+            .map_err(|error| KeeperSignerError::Remote(error.to_string()))?;
+        if signer != self.address {
+            return Err(KeeperSignerError::Remote(format!(
+                "transaction was signed by {signer:?}, expected {:?}",
+                self.address
+            )));
+        }
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(KeeperSignerError::Remote(String::from(
+            "typed data signing is not supported",
+        )))
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+/// Every way the keeper can sign, so `KeeperProvider` isn't tied to a key held in memory
+#[derive(Clone, Debug)]
+pub enum KeeperSigner {
+    Local(LocalWallet),
+    Remote(RemoteSigner),
+}
+
+impl KeeperSigner {
+    pub fn from_config(signer_config: &SignerConfig) -> Result<Self> {
+        let signer = match signer_config {
+            SignerConfig::PrivateKey(private_key) => KeeperSigner::Local(private_key.parse()?),
+            SignerConfig::Keystore {
+                path,
+                password_file,
+            } => {
+                let password = std::fs::read_to_string(password_file).with_context(|| {
+                    format!("Unable to read keystore password from {password_file}")
+                })?;
+                let wallet = LocalWallet::decrypt_keystore(path, password.trim_end())
+                    .with_context(|| format!("Unable to decrypt keystore {path}"))?;
+                KeeperSigner::Local(wallet)
+            }
+            SignerConfig::Remote { url, address } => {
+                KeeperSigner::Remote(RemoteSigner::new(url.clone(), *address))
+            }
+        };
+        Ok(signer)
+    }
+}
+
+#[async_trait]
+impl Signer for KeeperSigner {
+    type Error = KeeperSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            KeeperSigner::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            KeeperSigner::Remote(remote) => remote.sign_message(message).await,
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            KeeperSigner::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            KeeperSigner::Remote(remote) => remote.sign_transaction(tx).await,
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            KeeperSigner::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            KeeperSigner::Remote(remote) => remote.sign_typed_data(payload).await,
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            KeeperSigner::Local(wallet) => wallet.address(),
+            KeeperSigner::Remote(remote) => remote.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            KeeperSigner::Local(wallet) => wallet.chain_id(),
+            KeeperSigner::Remote(remote) => remote.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            KeeperSigner::Local(wallet) => KeeperSigner::Local(wallet.with_chain_id(chain_id)),
+            KeeperSigner::Remote(remote) => KeeperSigner::Remote(remote.with_chain_id(chain_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{mock_http_server, request_body};
+    use ethers::core::rand::thread_rng;
+
+    const PRIVATE_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn wallet() -> LocalWallet {
+        PRIVATE_KEY
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(11155111u64)
+    }
+
+    fn transaction() -> TypedTransaction {
+        Eip1559TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .data(vec![0x12, 0x34])
+            .nonce(7)
+            .gas(100_000)
+            .max_fee_per_gas(30_000_000_000u64)
+            .max_priority_fee_per_gas(1_000_000_000u64)
+            .into()
+    }
+
+    #[test]
+    fn keystores_are_unlocked_with_a_password_file() {
+        let dir = std::env::temp_dir().join(format!("azos-keeper-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let private_key = ethers::utils::hex::decode(PRIVATE_KEY).unwrap();
+        LocalWallet::encrypt_keystore(
+            &dir,
+            &mut thread_rng(),
+            private_key,
+            "hunter2",
+            Some("keeper"),
+        )
+        .unwrap();
+        let password_file = dir.join("password");
+        std::fs::write(&password_file, "hunter2\n").unwrap();
+
+        let signer = KeeperSigner::from_config(&SignerConfig::Keystore {
+            path: dir.join("keeper").to_string_lossy().to_string(),
+            password_file: password_file.to_string_lossy().to_string(),
+        })
+        .unwrap();
+        assert_eq!(signer.address(), wallet().address());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn remote_signatures_are_decoded_from_the_raw_transaction() {
+        let mut tx = transaction();
+        tx.set_from(wallet().address());
+        tx.set_chain_id(11155111u64);
+        let signature = wallet().sign_transaction(&tx).await.unwrap();
+        let raw_tx = tx.rlp_signed(&signature);
+        let (url, handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 1, "result": raw_tx})).await;
+
+        let remote = RemoteSigner::new(url, wallet().address()).with_chain_id(11155111u64);
+        let remote_signature = remote.sign_transaction(&transaction()).await.unwrap();
+        assert_eq!(
+            remote_signature.recover(tx.sighash()).unwrap(),
+            wallet().address()
+        );
+
+        let body = request_body(&handle.await.unwrap());
+        assert_eq!(body["method"], "eth_signTransaction");
+        assert_eq!(body["params"][0]["from"], json!(wallet().address()));
+    }
+
+    #[tokio::test]
+    async fn remote_signatures_from_another_key_are_rejected() {
+        let other = LocalWallet::new(&mut thread_rng()).with_chain_id(11155111u64);
+        let mut tx = transaction();
+        tx.set_from(wallet().address());
+        tx.set_chain_id(11155111u64);
+        let raw_tx = tx.rlp_signed(&other.sign_transaction(&tx).await.unwrap());
+        let (url, _handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 1, "result": {"raw": raw_tx}})).await;
+
+        let remote = RemoteSigner::new(url, wallet().address()).with_chain_id(11155111u64);
+        let error = remote.sign_transaction(&transaction()).await.unwrap_err();
+        assert!(error.to_string().contains("expected"));
+    }
+}
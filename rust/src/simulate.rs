@@ -0,0 +1,450 @@
+use crate::config::{self, Config};
+use crate::contracts::uniswap_v2_factory::UniswapV2Factory;
+use crate::contracts::uniswap_v2_pair::UniswapV2Pair;
+use crate::keeper::Keeper;
+use crate::rpc::FailoverClient;
+use crate::types::keeper::KeeperAction;
+use crate::types::token::TokenPair;
+use crate::utils::decimal::decimal_to_u256;
+use crate::utils::time::get_swap_deadline;
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use clap::Args;
+use ethers::prelude::*;
+use ethers::providers::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use rust_decimal::Decimal;
+use std::fmt;
+use std::sync::Arc;
+
+/// Where a Uniswap V2 pair keeps its packed reserves and last block timestamp
+const RESERVES_SLOT: u64 = 8;
+
+/// A read-only view of the chain, pinned to a block and with storage overridden, for what-if runs.  There's no
+/// signer behind it, so nothing it's handed can be sent.
+#[derive(Debug)]
+pub struct SimulationProvider<P> {
+    inner: Provider<P>,
+    block: Option<BlockId>,
+    state: Option<spoof::State>,
+}
+
+impl<P: JsonRpcClient> SimulationProvider<P> {
+    pub fn new(inner: Provider<P>, block: Option<u64>, state: Option<spoof::State>) -> Self {
+        SimulationProvider {
+            inner,
+            block: block.map(BlockId::from),
+            state,
+        }
+    }
+}
+
+#[async_trait]
+impl<P: JsonRpcClient + 'static> Middleware for SimulationProvider<P> {
+    type Error = ProviderError;
+    type Provider = P;
+    type Inner = Provider<P>;
+
+    fn inner(&self) -> &Provider<P> {
+        &self.inner
+    }
+
+    async fn call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<Bytes, ProviderError> {
+        let mut call = self.inner.call_raw(tx);
+        if let Some(block) = self.block.or(block) {
+            call = call.block(block);
+        }
+        match &self.state {
+            Some(state) => call.state(state).await,
+            None => call.await,
+        }
+    }
+
+    /**
+     * Estimates with the overrides passed as `eth_estimateGas`'s third parameter.  Nodes that don't take one fail
+     * the estimate, rather than giving a number for the chain as it really is.
+     */
+    async fn estimate_gas(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<U256, ProviderError> {
+        let block = self.block.or(block);
+        match &self.state {
+            Some(state) => {
+                let block = block.unwrap_or(BlockId::Number(BlockNumber::Latest));
+                self.inner
+                    .request("eth_estimateGas", (tx, block, state))
+                    .await
+            }
+            None => self.inner.estimate_gas(tx, block).await,
+        }
+    }
+}
+
+/**
+ * The value of a pair's reserves slot, reserve0 and reserve1 taking 112 bits each under the 32 bit timestamp.
+ */
+pub fn pack_reserves(reserve_0: U256, reserve_1: U256, block_timestamp_last: U256) -> Result<H256> {
+    let max_reserve = (U256::one() << 112) - 1;
+    if reserve_0 > max_reserve || reserve_1 > max_reserve {
+        bail!("Reserves have to fit in 112 bits");
+    }
+    let packed = reserve_0 | (reserve_1 << 112) | (block_timestamp_last << 224);
+    Ok(H256::from_uint(&packed))
+}
+
+/**
+ * A state override giving the pair of `token_pair` the reserves passed, in token units.  The pair's last block
+ * timestamp is kept as it is at `block`.
+ */
+pub async fn reserves_override<M: Middleware + 'static>(
+    config: &Config,
+    provider: Arc<M>,
+    token_pair: &TokenPair,
+    stable_coin_reserve: Decimal,
+    system_coin_reserve: Decimal,
+    block: Option<u64>,
+) -> Result<spoof::State> {
+    let block = block.map(BlockId::from);
+    let uniswap_factory = UniswapV2Factory::new(config.uniswap_factory_address, provider.clone());
+    let pair_address = uniswap_factory
+        .get_pair(token_pair.token_0.address, token_pair.token_1.address)
+        .call()
+        .await?;
+    if pair_address.is_zero() {
+        bail!("There is no Uniswap V2 pair for {}", token_pair.symbol);
+    }
+    let pair = UniswapV2Pair::new(pair_address, provider.clone());
+    let mut token_0_call = pair.token_0();
+    if let Some(block) = block {
+        token_0_call = token_0_call.block(block);
+    }
+    let stable_coin_is_token_0 = token_0_call.call().await? == token_pair.token_0.address;
+
+    let slot = H256::from_low_u64_be(RESERVES_SLOT);
+    let current = provider
+        .get_storage_at(pair_address, slot, block)
+        .await
+        .map_err(|error| anyhow!("Unable to read the reserves of {pair_address:?}: {error}"))?;
+    let block_timestamp_last = current.into_uint() >> 224;
+
+    let stable_coin_reserve = decimal_to_u256(stable_coin_reserve, token_pair.token_0.decimals);
+    let system_coin_reserve = decimal_to_u256(system_coin_reserve, token_pair.token_1.decimals);
+    let (reserve_0, reserve_1) = if stable_coin_is_token_0 {
+        (stable_coin_reserve, system_coin_reserve)
+    } else {
+        (system_coin_reserve, stable_coin_reserve)
+    };
+    let mut state = spoof::state();
+    state.account(pair_address).store(
+        slot,
+        pack_reserves(reserve_0, reserve_1, block_timestamp_last)?,
+    );
+    Ok(state)
+}
+
+/// An action the keeper would take and the transaction it would send for it
+pub struct SimulatedAction {
+    pub token_pair: String,
+    pub action: KeeperAction,
+    pub price_after: Option<Decimal>,
+    pub to: Option<NameOrAddress>,
+    pub calldata: Bytes,
+    pub gas: Result<U256, String>, // Fails when the sender can't act on the module, or the node won't take the overrides
+}
+
+impl fmt::Display for SimulatedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let swap_details = self.action.swap_details();
+        let action = match self.action {
+            KeeperAction::ExpandAndBuy(_) => "expandAndBuy",
+            KeeperAction::ContractAndSell(_) => "contractAndSell",
+            KeeperAction::None(_) => "none",
+        };
+        writeln!(
+            f,
+            "{} on {:?} via {}",
+            self.token_pair, swap_details.venue, swap_details.adapter_name
+        )?;
+        writeln!(f, "  action:        {action}")?;
+        writeln!(
+            f,
+            "  sell:          {} {}",
+            swap_details.amount_to_sell, swap_details.token_to_sell.symbol
+        )?;
+        writeln!(
+            f,
+            "  buy at least:  {} {}",
+            swap_details.amount_to_buy_min, swap_details.token_to_buy.symbol
+        )?;
+        writeln!(f, "  price:         {}", swap_details.dex_price)?;
+        match self.price_after {
+            Some(price_after) => writeln!(f, "  price after:   {}", price_after.round_dp(8))?,
+            None => writeln!(f, "  price after:   not simulated for this venue")?,
+        }
+        match &self.to {
+            Some(NameOrAddress::Address(to)) => writeln!(f, "  to:            {to:?}")?,
+            _ => writeln!(f, "  to:            unknown")?,
+        }
+        writeln!(f, "  calldata:      {}", self.calldata)?;
+        match &self.gas {
+            Ok(gas) => write!(f, "  estimated gas: {gas}"),
+            Err(error) => write!(f, "  estimated gas: unavailable, {error}"),
+        }
+    }
+}
+
+/**
+ * Sets the keeper up over `provider`.  Everything is read through it, even with a quorum configured, as the other
+ * endpoints would see neither the pinned block nor the overrides.
+ */
+pub async fn simulation_keeper<P: JsonRpcClient + 'static>(
+    mut config: Config,
+    provider: SimulationProvider<P>,
+) -> Result<Keeper<SimulationProvider<P>>> {
+    config.rpc_quorum = None;
+    Keeper::with_provider(config, Arc::new(provider)).await
+}
+
+/**
+ * Evaluates every pair the way a tick would and builds the calls for the resulting actions, without sending them.
+ * Gas is estimated as `from`, which has to be allowed to act on the stability module.
+ */
+pub async fn simulate<P: JsonRpcClient + 'static>(
+    keeper: &Keeper<SimulationProvider<P>>,
+    from: Option<Address>,
+) -> Result<Vec<SimulatedAction>> {
+    let provider = keeper.provider();
+    let block = provider
+        .block
+        .unwrap_or(BlockId::Number(BlockNumber::Latest));
+    let block_timestamp = provider
+        .get_block(block)
+        .await?
+        .ok_or_else(|| anyhow!("Block {block:?} not found"))?
+        .timestamp;
+    let deadline = get_swap_deadline(block_timestamp, keeper.config().swap_deadline_seconds);
+
+    let mut simulated = vec![];
+    for token_pair in &keeper.config().token_pairs {
+        for action in keeper.evaluate_pair(token_pair).await? {
+            let mut call = keeper.build_call(token_pair, &action, deadline)?;
+            if let Some(from) = from {
+                call.tx.set_from(from);
+            }
+            let gas = provider
+                .estimate_gas(&call.tx, None)
+                .await
+                .map_err(|error| error.to_string());
+            simulated.push(SimulatedAction {
+                token_pair: token_pair.symbol.clone(),
+                price_after: keeper.price_after(token_pair, &action).await?,
+                to: call.tx.to().cloned(),
+                calldata: call.tx.data().cloned().unwrap_or_default(),
+                gas,
+                action,
+            });
+        }
+    }
+    Ok(simulated)
+}
+
+#[derive(Args)]
+pub struct SimulateArgs {
+    /// Read the chain as of this block instead of the latest
+    #[arg(long)]
+    block: Option<u64>,
+    /// Pair whose reserves to replace, the first configured pair by default
+    #[arg(long)]
+    pair: Option<String>,
+    /// Stable coin reserve to give the pair, in tokens
+    #[arg(long, requires = "system_coin_reserve")]
+    stable_coin_reserve: Option<Decimal>,
+    /// System coin reserve to give the pair, in tokens
+    #[arg(long, requires = "stable_coin_reserve")]
+    system_coin_reserve: Option<Decimal>,
+    /// Account to estimate gas as, it has to be allowed to act on the stability module
+    #[arg(long)]
+    from: Option<Address>,
+}
+
+/**
+ * Prints what the keeper would do at the block and with the reserves given.
+ */
+pub async fn command(args: SimulateArgs) -> Result<()> {
+    let config = config::generate_config();
+    let state = match (args.stable_coin_reserve, args.system_coin_reserve) {
+        (Some(stable_coin_reserve), Some(system_coin_reserve)) => {
+            let token_pair = match &args.pair {
+                Some(symbol) => config
+                    .token_pairs
+                    .iter()
+                    .find(|token_pair| &token_pair.symbol == symbol)
+                    .ok_or_else(|| anyhow!("No pair named {symbol} is configured"))?,
+                None => &config.token_pairs[0],
+            };
+            let reader = Arc::new(Provider::new(FailoverClient::new(&config.rpc_urls)?));
+            Some(
+                reserves_override(
+                    &config,
+                    reader,
+                    token_pair,
+                    stable_coin_reserve,
+                    system_coin_reserve,
+                    args.block,
+                )
+                .await?,
+            )
+        }
+        _ => None,
+    };
+    let provider = SimulationProvider::new(
+        Provider::new(FailoverClient::new(&config.rpc_urls)?),
+        args.block,
+        state,
+    );
+
+    let keeper = simulation_keeper(config, provider).await?;
+    let simulated = simulate(&keeper, args.from).await?;
+    if simulated.is_empty() {
+        println!("No action needed");
+    }
+    for action in simulated {
+        println!("{action}\n");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{mock_chain, mock_http_server, request_body, test_config, units};
+    use serde_json::json;
+
+    #[test]
+    fn reserves_pack_under_the_timestamp() {
+        let packed = pack_reserves(U256::from(3), U256::from(5), U256::from(7)).unwrap();
+        let value = packed.into_uint();
+        assert_eq!(value & ((U256::one() << 112) - 1), U256::from(3));
+        assert_eq!((value >> 112) & ((U256::one() << 112) - 1), U256::from(5));
+        assert_eq!(value >> 224, U256::from(7));
+
+        assert!(pack_reserves(U256::one() << 112, U256::zero(), U256::zero()).is_err());
+    }
+
+    #[tokio::test]
+    async fn calls_are_pinned_and_overridden() {
+        let (url, handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 1, "result": "0x"})).await;
+        let pair_address = Address::repeat_byte(0x40);
+        let mut state = spoof::state();
+        state.account(pair_address).store(
+            H256::from_low_u64_be(RESERVES_SLOT),
+            H256::repeat_byte(0x01),
+        );
+        let provider = SimulationProvider::new(
+            Provider::<Http>::try_from(url).unwrap(),
+            Some(26),
+            Some(state),
+        );
+
+        let tx = TransactionRequest::new().to(pair_address).into();
+        provider.call(&tx, None).await.unwrap();
+
+        let body = request_body(&handle.await.unwrap());
+        assert_eq!(body["method"], "eth_call");
+        assert_eq!(body["params"][1], "0x1a");
+        let slot = format!("{:?}", H256::from_low_u64_be(RESERVES_SLOT));
+        assert_eq!(
+            body["params"][2][format!("{pair_address:?}")]["stateDiff"][slot],
+            format!("{:?}", H256::repeat_byte(0x01))
+        );
+    }
+
+    #[tokio::test]
+    async fn gas_is_estimated_with_the_overrides() {
+        let (url, handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 1, "result": "0x5208"})).await;
+        let pair_address = Address::repeat_byte(0x40);
+        let mut state = spoof::state();
+        state.account(pair_address).store(
+            H256::from_low_u64_be(RESERVES_SLOT),
+            H256::repeat_byte(0x01),
+        );
+        let provider = SimulationProvider::new(
+            Provider::<Http>::try_from(url).unwrap(),
+            Some(26),
+            Some(state),
+        );
+
+        let tx = TransactionRequest::new().to(pair_address).into();
+        assert_eq!(
+            provider.estimate_gas(&tx, None).await.unwrap(),
+            U256::from(21_000)
+        );
+
+        let body = request_body(&handle.await.unwrap());
+        assert_eq!(body["method"], "eth_estimateGas");
+        assert_eq!(body["params"][1], "0x1a");
+        let slot = format!("{:?}", H256::from_low_u64_be(RESERVES_SLOT));
+        assert_eq!(
+            body["params"][2][format!("{pair_address:?}")]["stateDiff"][slot],
+            format!("{:?}", H256::repeat_byte(0x01))
+        );
+    }
+
+    #[tokio::test]
+    async fn estimates_without_override_support_are_unavailable() {
+        let (url, _handle) = mock_http_server(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": {"code": -32602, "message": "too many arguments, want at most 2"}
+        }))
+        .await;
+        let provider = SimulationProvider::new(
+            Provider::<Http>::try_from(url).unwrap(),
+            None,
+            Some(spoof::state()),
+        );
+
+        let tx = TransactionRequest::new()
+            .to(Address::repeat_byte(0x40))
+            .into();
+        let result = provider.estimate_gas(&tx, None).await;
+        assert!(result.is_err_and(|error| error.to_string().contains("too many arguments")));
+    }
+
+    #[tokio::test]
+    async fn simulating_needs_no_authorized_account() {
+        let mut config = test_config();
+        config.authorization_required = true;
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.mine(H256::repeat_byte(0xd0), vec![]);
+        let provider = SimulationProvider::new(Provider::new(chain.clone()), None, None);
+
+        let keeper = simulation_keeper(config, provider).await.unwrap();
+        assert!(simulate(&keeper, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reserves_are_read_through_the_simulation_with_a_quorum() {
+        let mut config = test_config();
+        // Endpoints nothing listens on, reading from them would fail
+        config.rpc_urls = vec![
+            String::from("http://127.0.0.1:1"),
+            String::from("http://127.0.0.1:2"),
+        ];
+        config.rpc_quorum = Some(2);
+        let chain = mock_chain(&config, units("1000000", 18), units("1000000", 18), true);
+        chain.mine(H256::repeat_byte(0xd1), vec![]);
+        let provider = SimulationProvider::new(Provider::new(chain.clone()), None, None);
+
+        let keeper = simulation_keeper(config, provider).await.unwrap();
+        assert!(simulate(&keeper, None).await.unwrap().is_empty());
+    }
+}
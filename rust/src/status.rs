@@ -0,0 +1,423 @@
+use crate::config::{self, Config};
+use crate::contracts::erc20::ERC20;
+use crate::keeper::{self, Keeper};
+use crate::types::provider::StabilityModule;
+use crate::utils::decimal::{i256_to_decimal, u256_to_decimal};
+use anyhow::{anyhow, Result};
+use clap::Args;
+use ethers::prelude::*;
+use ethers::utils::format_bytes32_string;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+
+/// Where a price sits against the allowed range
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Band {
+    Below,
+    Within,
+    Above,
+}
+
+impl Band {
+    pub fn of(price: Decimal, ratio_range_allowed: (Decimal, Decimal)) -> Self {
+        let (low, high) = ratio_range_allowed;
+        if price < low {
+            Band::Below
+        } else if price > high {
+            Band::Above
+        } else {
+            Band::Within
+        }
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Band::Below => write!(f, "below band, contraction due"),
+            Band::Within => write!(f, "within band"),
+            Band::Above => write!(f, "above band, expansion due"),
+        }
+    }
+}
+
+/// The stability module's state and parameters, debt in system coin and deposits in collateral
+#[derive(Debug, Serialize)]
+pub struct ModuleStatus {
+    pub address: Address,
+    pub debt: Decimal,
+    pub debt_ceiling: Decimal,
+    pub deposits: Decimal,
+    pub max_deposit: Decimal,
+    pub basis_fee: Decimal,
+    pub system_coin: Address,
+    pub authorized_collateral: Address,
+    pub scaling_factor: Decimal,
+}
+
+/// A configured adapter next to what the module has registered under its name
+#[derive(Debug, Serialize)]
+pub struct AdapterStatus {
+    pub name: String,
+    pub configured: Address,
+    pub registered: Address,
+}
+
+impl AdapterStatus {
+    pub fn matches(&self) -> bool {
+        self.configured == self.registered
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolStatus {
+    pub pair: String,
+    pub venue: String,
+    pub price: Decimal,
+    pub band: Band,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenBalance {
+    pub symbol: String,
+    pub address: Address,
+    pub balance: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletStatus {
+    pub address: Address,
+    pub eth: Decimal,
+    pub tokens: Vec<TokenBalance>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub block_number: u64,
+    pub module: ModuleStatus,
+    pub adapters: Vec<AdapterStatus>,
+    pub pools: Vec<PoolStatus>,
+    pub pools_error: Option<String>, // Pools aren't priced when the keeper can't start, e.g. over an adapter mismatch, or can't quote them
+    pub health: Option<String>, // The keeper's health as its ticks would report it, when it could start
+    pub wallet: WalletStatus,
+}
+
+async fn token_decimals<M: Middleware + 'static>(provider: &Arc<M>, token: Address) -> Result<u64> {
+    Ok(ERC20::new(token, provider.clone())
+        .decimals()
+        .call()
+        .await? as u64)
+}
+
+pub async fn module_status<M: Middleware + 'static>(
+    stability_module: &StabilityModule<M>,
+) -> Result<ModuleStatus> {
+    let provider = stability_module.client();
+    let system_coin = stability_module.system_coin().call().await?;
+    let authorized_collateral = stability_module.authorized_collateral().call().await?;
+    let system_coin_decimals = token_decimals(&provider, system_coin).await?;
+    let collateral_decimals = token_decimals(&provider, authorized_collateral).await?;
+
+    Ok(ModuleStatus {
+        address: stability_module.address(),
+        debt: i256_to_decimal(
+            stability_module.get_debt().call().await?,
+            system_coin_decimals,
+        ),
+        debt_ceiling: u256_to_decimal(
+            stability_module.debt_ceiling().call().await?,
+            system_coin_decimals,
+        ),
+        deposits: u256_to_decimal(
+            stability_module.get_deposits().call().await?,
+            collateral_decimals,
+        ),
+        max_deposit: u256_to_decimal(
+            stability_module.max_deposit().call().await?,
+            collateral_decimals,
+        ),
+        basis_fee: u256_to_decimal(stability_module.basis_fee().call().await?, 0),
+        system_coin,
+        authorized_collateral,
+        scaling_factor: u256_to_decimal(stability_module.scaling_factor().call().await?, 0),
+    })
+}
+
+/**
+ * Looks up each configured adapter on the module, a zero address meaning nothing is registered under the name.
+ */
+pub async fn adapter_statuses<M: Middleware + 'static>(
+    config: &Config,
+    stability_module: &StabilityModule<M>,
+) -> Result<Vec<AdapterStatus>> {
+    let mut statuses = vec![];
+    for adapter in &config.adapters {
+        let name = format_bytes32_string(&adapter.name)?;
+        statuses.push(AdapterStatus {
+            name: adapter.name.clone(),
+            configured: adapter.address,
+            registered: stability_module.get_adapter(name).call().await?,
+        });
+    }
+    Ok(statuses)
+}
+
+/**
+ * The wallet's ETH and its balance of every configured token.
+ */
+pub async fn wallet_status<M: Middleware + 'static>(
+    config: &Config,
+    provider: &Arc<M>,
+    address: Address,
+) -> Result<WalletStatus> {
+    let eth = provider
+        .get_balance(address, None)
+        .await
+        .map_err(|error| anyhow!("Unable to read the ETH balance: {error}"))?;
+    let mut tokens: Vec<TokenBalance> = vec![];
+    for token_pair in &config.token_pairs {
+        for token in [&token_pair.token_0, &token_pair.token_1] {
+            if tokens
+                .iter()
+                .any(|balance| balance.address == token.address)
+            {
+                continue;
+            }
+            let balance = ERC20::new(token.address, provider.clone())
+                .balance_of(address)
+                .call()
+                .await?;
+            tokens.push(TokenBalance {
+                symbol: token.symbol.clone(),
+                address: token.address,
+                balance: u256_to_decimal(balance, token.decimals),
+            });
+        }
+    }
+    Ok(WalletStatus {
+        address,
+        eth: u256_to_decimal(eth, 18),
+        tokens,
+    })
+}
+
+/**
+ * Gathers the module, adapters and wallet, then prices the pools through a keeper set up on `provider`.
+ */
+pub async fn status<M: Middleware + 'static>(config: Config, provider: Arc<M>) -> Result<Status> {
+    let block_number = provider
+        .get_block_number()
+        .await
+        .map_err(|error| anyhow!("Unable to read the block number: {error}"))?
+        .as_u64();
+    let stability_module = StabilityModule::new(config.stability_module_address, provider.clone());
+    let module = module_status(&stability_module).await?;
+    let adapters = adapter_statuses(&config, &stability_module).await?;
+    let wallet = wallet_status(
+        &config,
+        &provider,
+        provider.default_sender().unwrap_or_default(),
+    )
+    .await?;
+
+    let (pools, pools_error, health) = match Keeper::with_provider(config, provider).await {
+        Ok(mut keeper) => {
+            let health = match keeper.check_health(block_number).await {
+                Ok(_) => keeper.health().to_string(),
+                Err(error) => format!("unknown, {error}"),
+            };
+            let mut pools = vec![];
+            let mut pools_error = None;
+            for token_pair in &keeper.config().token_pairs {
+                let prices = match keeper.venue_prices(token_pair).await {
+                    Ok(prices) => prices,
+                    Err(error) => {
+                        pools_error = Some(format!("{}: {error}", token_pair.symbol));
+                        continue;
+                    }
+                };
+                for (venue, price) in prices {
+                    pools.push(PoolStatus {
+                        pair: token_pair.symbol.clone(),
+                        venue: format!("{venue:?}"),
+                        price,
+                        band: Band::of(price, keeper.config().ratio_range_allowed),
+                    });
+                }
+            }
+            (pools, pools_error, Some(health))
+        }
+        Err(error) => (vec![], Some(error.to_string()), None),
+    };
+    Ok(Status {
+        block_number,
+        module,
+        adapters,
+        pools,
+        pools_error,
+        health,
+        wallet,
+    })
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let module = &self.module;
+        writeln!(
+            f,
+            "Stability module {:?} at block {}",
+            module.address, self.block_number
+        )?;
+        writeln!(
+            f,
+            "  debt:           {} of {} ceiling",
+            module.debt, module.debt_ceiling
+        )?;
+        writeln!(
+            f,
+            "  deposits:       {} of {} max",
+            module.deposits, module.max_deposit
+        )?;
+        writeln!(f, "  basis fee:      {}", module.basis_fee)?;
+        writeln!(f, "  system coin:    {:?}", module.system_coin)?;
+        writeln!(f, "  collateral:     {:?}", module.authorized_collateral)?;
+        writeln!(f, "  scaling factor: {}", module.scaling_factor)?;
+
+        writeln!(f, "Adapters")?;
+        for adapter in &self.adapters {
+            if adapter.matches() {
+                writeln!(f, "  {:<14}  {:?}", adapter.name, adapter.configured)?;
+            } else if adapter.registered.is_zero() {
+                writeln!(
+                    f,
+                    "  {:<14}  {:?}, not registered",
+                    adapter.name, adapter.configured
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "  {:<14}  {:?}, registered as {:?}",
+                    adapter.name, adapter.configured, adapter.registered
+                )?;
+            }
+        }
+
+        writeln!(f, "Pools")?;
+        if let Some(error) = &self.pools_error {
+            writeln!(f, "  unavailable, {error}")?;
+        }
+        for pool in &self.pools {
+            writeln!(
+                f,
+                "  {} on {}  {}  {}",
+                pool.pair,
+                pool.venue,
+                pool.price.round_dp(6),
+                pool.band
+            )?;
+        }
+
+        writeln!(f, "Keeper")?;
+        match &self.health {
+            Some(health) => writeln!(f, "  health:         {health}")?,
+            None => writeln!(f, "  health:         unavailable")?,
+        }
+
+        writeln!(f, "Wallet {:?}", self.wallet.address)?;
+        write!(f, "  {:<6}  {}", "ETH", self.wallet.eth.round_dp(6))?;
+        for token in &self.wallet.tokens {
+            write!(f, "\n  {:<6}  {}", token.symbol, token.balance.round_dp(6))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// Print JSON instead of a readable report
+    #[arg(long)]
+    json: bool,
+}
+
+/**
+ * Prints the status as seen from the keeper's account.
+ */
+pub async fn command(args: StatusArgs) -> Result<()> {
+    let config = config::generate_config();
+    let provider = Arc::new(keeper::connect(&config, None)?);
+    let status = status(config, provider).await?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        println!("{status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::azos_stability_module::{
+        AuthorizedCollateralCall, BasisFeeCall, DebtCeilingCall, GetAdapterCall, GetDebtCall,
+        GetDepositsCall, MaxDepositCall, ScalingFactorCall, SystemCoinCall,
+    };
+    use crate::contracts::erc20::DecimalsCall;
+    use crate::test_utils::{test_config, MockChain};
+    use ethers::utils::parse_units;
+
+    #[test]
+    fn prices_are_placed_against_the_allowed_range() {
+        let allowed = test_config().ratio_range_allowed;
+        assert_eq!(
+            Band::of(Decimal::from_str_exact("0.995").unwrap(), allowed),
+            Band::Below
+        );
+        assert_eq!(
+            Band::of(Decimal::from_str_exact("0.996").unwrap(), allowed),
+            Band::Within
+        );
+        assert_eq!(
+            Band::of(Decimal::from_str_exact("1.003").unwrap(), allowed),
+            Band::Above
+        );
+    }
+
+    #[tokio::test]
+    async fn module_and_adapters_are_read_in_token_units() {
+        let config = test_config();
+        let module = config.stability_module_address;
+        let (usdc, zai) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02));
+        let chain = Arc::new(MockChain::default());
+        chain.on_any_call::<SystemCoinCall>(module, zai);
+        chain.on_any_call::<AuthorizedCollateralCall>(module, usdc);
+        chain.on_any_call::<DecimalsCall>(zai, 18u8);
+        chain.on_any_call::<DecimalsCall>(usdc, 6u8);
+        chain.on_any_call::<GetDebtCall>(
+            module,
+            I256::from_raw(parse_units("-250", 18).unwrap().into()),
+        );
+        chain.on_any_call::<DebtCeilingCall>(module, U256::from(parse_units("1000", 18).unwrap()));
+        chain.on_any_call::<GetDepositsCall>(module, U256::from(500_000_000u64));
+        chain.on_any_call::<MaxDepositCall>(module, U256::from(2_000_000_000u64));
+        chain.on_any_call::<BasisFeeCall>(module, U256::from(5));
+        chain.on_any_call::<ScalingFactorCall>(module, U256::exp10(12));
+        chain.on_any_call::<GetAdapterCall>(module, Address::zero());
+        let stability_module = StabilityModule::new(module, chain.provider());
+
+        let status = module_status(&stability_module).await.unwrap();
+        assert_eq!(status.debt, Decimal::from(-250));
+        assert_eq!(status.debt_ceiling, Decimal::from(1000));
+        assert_eq!(status.deposits, Decimal::from(500));
+        assert_eq!(status.max_deposit, Decimal::from(2000));
+        assert_eq!(status.scaling_factor, Decimal::from(1_000_000_000_000u64));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["debt"], "-250");
+
+        let adapters = adapter_statuses(&config, &stability_module).await.unwrap();
+        assert_eq!(adapters.len(), 1);
+        assert!(!adapters[0].matches());
+    }
+}
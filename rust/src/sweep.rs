@@ -0,0 +1,451 @@
+use crate::backtest::{
+    load_events, run_backtest, BacktestParams, BacktestReport, CostArgs, PoolEvent,
+};
+use crate::config;
+use anyhow::{Context, Result};
+use clap::Args;
+use ethers::core::rand::rngs::StdRng;
+use ethers::core::rand::{Rng, SeedableRng};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Values to try for each swept parameter, an empty list keeps the base value
+#[derive(Clone, Debug, Default)]
+pub struct SweepSpace {
+    pub allowed_low: Vec<Decimal>,
+    pub allowed_high: Vec<Decimal>,
+    pub target_low: Vec<Decimal>,
+    pub target_high: Vec<Decimal>,
+    pub slippage_bps: Vec<u32>,
+    pub max_trade: Vec<Option<Decimal>>,
+}
+
+fn or_base<T: Clone>(values: &[T], base: T) -> Vec<T> {
+    if values.is_empty() {
+        vec![base]
+    } else {
+        values.to_vec()
+    }
+}
+
+fn draw_decimal(rng: &mut StdRng, values: &[Decimal], base: Decimal) -> Decimal {
+    let (Some(low), Some(high)) = (values.iter().min(), values.iter().max()) else {
+        return base;
+    };
+    let fraction = Decimal::from_f64(rng.gen::<f64>()).unwrap_or_default();
+    (*low + (*high - *low) * fraction).round_dp(4)
+}
+
+/**
+ * The targets have to sit inside the allowed range, on either side of the peg.
+ */
+fn is_valid(params: &BacktestParams) -> bool {
+    let (allowed_low, allowed_high) = params.ratio_range_allowed;
+    let (target_low, target_high) = params.ratio_range_targets;
+    allowed_low <= target_low
+        && target_low < Decimal::ONE
+        && Decimal::ONE < target_high
+        && target_high <= allowed_high
+}
+
+impl SweepSpace {
+    /**
+     * Every combination of the values, leaving out the ones whose targets fall outside the allowed range.
+     */
+    pub fn grid(&self, base: &BacktestParams) -> Vec<BacktestParams> {
+        let mut grid = vec![];
+        for allowed_low in or_base(&self.allowed_low, base.ratio_range_allowed.0) {
+            for allowed_high in or_base(&self.allowed_high, base.ratio_range_allowed.1) {
+                for target_low in or_base(&self.target_low, base.ratio_range_targets.0) {
+                    for target_high in or_base(&self.target_high, base.ratio_range_targets.1) {
+                        for slippage_bps in or_base(&self.slippage_bps, base.slippage_bps) {
+                            for max_trade in or_base(&self.max_trade, base.max_trade) {
+                                grid.push(BacktestParams {
+                                    ratio_range_allowed: (allowed_low, allowed_high),
+                                    ratio_range_targets: (target_low, target_high),
+                                    slippage_bps,
+                                    max_trade,
+                                    ..base.clone()
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        grid.retain(is_valid);
+        grid
+    }
+
+    /**
+     * Up to `samples` valid parameter sets, each range drawn uniformly between the smallest and largest value given.
+     * Trade caps are picked from the values as given.
+     */
+    pub fn random(&self, base: &BacktestParams, samples: usize, seed: u64) -> Vec<BacktestParams> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut sampled = vec![];
+        // Invalid draws are thrown away, give up rather than spin on a space with hardly any valid sets
+        for _ in 0..samples * 100 {
+            if sampled.len() == samples {
+                break;
+            }
+            let slippage_bps = match (
+                self.slippage_bps.iter().min(),
+                self.slippage_bps.iter().max(),
+            ) {
+                (Some(low), Some(high)) => rng.gen_range(*low..=*high),
+                _ => base.slippage_bps,
+            };
+            let max_trade = if self.max_trade.is_empty() {
+                base.max_trade
+            } else {
+                self.max_trade[rng.gen_range(0..self.max_trade.len())]
+            };
+            let params = BacktestParams {
+                ratio_range_allowed: (
+                    draw_decimal(&mut rng, &self.allowed_low, base.ratio_range_allowed.0),
+                    draw_decimal(&mut rng, &self.allowed_high, base.ratio_range_allowed.1),
+                ),
+                ratio_range_targets: (
+                    draw_decimal(&mut rng, &self.target_low, base.ratio_range_targets.0),
+                    draw_decimal(&mut rng, &self.target_high, base.ratio_range_targets.1),
+                ),
+                slippage_bps,
+                max_trade,
+                ..base.clone()
+            };
+            if is_valid(&params) {
+                sampled.push(params);
+            }
+        }
+        sampled
+    }
+}
+
+/// A parameter set and how it fared
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    pub params: BacktestParams,
+    pub report: BacktestReport,
+    pub dominated_by: usize, // How many other sets were at least as good on every measure and better on one
+}
+
+impl SweepResult {
+    pub fn capital_used(&self) -> Decimal {
+        self.report.peak_module_debt
+    }
+
+    fn dominates(&self, other: &SweepResult) -> bool {
+        let measures = |result: &SweepResult| {
+            (
+                result.report.simulated_mean_deviation(),
+                result.capital_used(),
+                -result.report.module_profit,
+            )
+        };
+        let (deviation, capital, loss) = measures(self);
+        let (other_deviation, other_capital, other_loss) = measures(other);
+        deviation <= other_deviation
+            && capital <= other_capital
+            && loss <= other_loss
+            && (deviation < other_deviation || capital < other_capital || loss < other_loss)
+    }
+}
+
+/**
+ * Backtests every parameter set, ranked by how few other sets beat them on peg stability, capital used and profit
+ * all at once.  Sets nothing beats come first, closest to peg first among equals.
+ */
+pub fn run_sweep(events: &[PoolEvent], candidates: Vec<BacktestParams>) -> Vec<SweepResult> {
+    info!(candidates = candidates.len(), "Sweeping parameter sets");
+    let results = candidates
+        .into_iter()
+        .map(|params| SweepResult {
+            report: run_backtest(events, &params),
+            params,
+            dominated_by: 0,
+        })
+        .collect();
+    rank(results)
+}
+
+/**
+ * Counts what dominates each result and orders them as `run_sweep` ranks them.
+ */
+fn rank(mut results: Vec<SweepResult>) -> Vec<SweepResult> {
+    for index in 0..results.len() {
+        results[index].dominated_by = results
+            .iter()
+            .filter(|other| other.dominates(&results[index]))
+            .count();
+    }
+    results.sort_by(|a, b| {
+        a.dominated_by.cmp(&b.dominated_by).then(
+            a.report
+                .simulated_mean_deviation()
+                .cmp(&b.report.simulated_mean_deviation()),
+        )
+    });
+    results
+}
+
+/**
+ * Writes the ranked results as CSV, keeper profit being its fees less gas at `eth_price` in the stable coin.
+ */
+pub fn write_csv(path: &Path, results: &[SweepResult], eth_price: Decimal) -> Result<()> {
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("Unable to create {}", path.display()))?;
+    writeln!(
+        file,
+        "rank,dominated_by,allowed_low,allowed_high,target_low,target_high,slippage_bps,max_trade,\
+         mean_deviation,max_deviation,blocks_off_peg,expansions,contractions,reverted,minted,burned,\
+         capital_used,module_profit,keeper_profit"
+    )?;
+    for (rank, result) in results.iter().enumerate() {
+        let (params, report) = (&result.params, &result.report);
+        let keeper_profit = report.keeper_fees - report.gas_cost_eth * eth_price;
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            rank + 1,
+            result.dominated_by,
+            params.ratio_range_allowed.0,
+            params.ratio_range_allowed.1,
+            params.ratio_range_targets.0,
+            params.ratio_range_targets.1,
+            params.slippage_bps,
+            params
+                .max_trade
+                .map_or(String::new(), |max_trade| max_trade.to_string()),
+            report.simulated_mean_deviation().round_dp(8),
+            report.simulated_max_deviation().round_dp(8),
+            report.simulated_blocks_off_peg(),
+            report.expansions,
+            report.contractions,
+            report.reverted,
+            report.system_coin_minted.round_dp(6),
+            report.system_coin_burned.round_dp(6),
+            result.capital_used().round_dp(6),
+            report.module_profit.round_dp(6),
+            keeper_profit.round_dp(6),
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Args)]
+pub struct SweepArgs {
+    /// JSONL dump of the pair's events, as saved by backtest
+    #[arg(long)]
+    events: PathBuf,
+    /// Lower bounds of the allowed range to try, comma separated
+    #[arg(long, value_delimiter = ',')]
+    allowed_low: Vec<Decimal>,
+    /// Upper bounds of the allowed range to try
+    #[arg(long, value_delimiter = ',')]
+    allowed_high: Vec<Decimal>,
+    /// Lower targets to try
+    #[arg(long, value_delimiter = ',')]
+    target_low: Vec<Decimal>,
+    /// Upper targets to try
+    #[arg(long, value_delimiter = ',')]
+    target_high: Vec<Decimal>,
+    /// Slippage bounds to try, in basis points
+    #[arg(long, value_delimiter = ',')]
+    slippage_bps: Vec<u32>,
+    /// Most system coin an action may mint or burn, 0 for no cap
+    #[arg(long, value_delimiter = ',')]
+    max_trade: Vec<Decimal>,
+    /// Sample this many sets at random between the smallest and largest values, instead of trying every combination
+    #[arg(long)]
+    random: Option<usize>,
+    /// Seed for --random, to reproduce a sweep
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Where to write the ranked results
+    #[arg(long, default_value = "sweep.csv")]
+    output: PathBuf,
+    /// ETH price in the stable coin, to weigh gas against keeper fees
+    #[arg(long, default_value = "2000")]
+    eth_price: Decimal,
+    #[command(flatten)]
+    costs: CostArgs,
+}
+
+/**
+ * Sweeps the parameter space given against a saved history, printing the best sets and writing them all as CSV.
+ * Nothing but the pair has to be configured.
+ */
+pub fn command(args: SweepArgs) -> Result<()> {
+    let config = config::generate_strategy_config();
+    let events = load_events(&args.events)?;
+    let base = args.costs.backtest_params(&config);
+    let space = SweepSpace {
+        allowed_low: args.allowed_low,
+        allowed_high: args.allowed_high,
+        target_low: args.target_low,
+        target_high: args.target_high,
+        slippage_bps: args.slippage_bps,
+        max_trade: args
+            .max_trade
+            .into_iter()
+            .map(|max_trade| (!max_trade.is_zero()).then_some(max_trade))
+            .collect(),
+    };
+    let candidates = match args.random {
+        Some(samples) => space.random(&base, samples, args.seed),
+        None => space.grid(&base),
+    };
+
+    let results = run_sweep(&events, candidates);
+    write_csv(&args.output, &results, args.eth_price)?;
+    for result in results.iter().take(5) {
+        println!(
+            "allowed={:?} targets={:?} slippage_bps={} max_trade={:?}\n{}\n",
+            result.params.ratio_range_allowed,
+            result.params.ratio_range_targets,
+            result.params.slippage_bps,
+            result.params.max_trade,
+            result.report
+        );
+    }
+    println!(
+        "Ranked {} parameter sets into {}",
+        results.len(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str_exact(value).unwrap()
+    }
+
+    fn base() -> BacktestParams {
+        BacktestParams {
+            ratio_range_allowed: (dec("0.996"), dec("1.002")),
+            ratio_range_targets: (dec("0.997"), dec("1.001")),
+            fee_rate: dec("0.003"),
+            stable_coin_decimals: 18,
+            system_coin_decimals: 18,
+            stable_coin_is_token_0: true,
+            slippage_bps: 50,
+            max_trade: None,
+            keeper_fee_bps: 0,
+            gas_per_action: 400_000,
+            gas_price_gwei: Decimal::from(20),
+        }
+    }
+
+    #[test]
+    fn grids_leave_out_targets_outside_the_allowed_range() {
+        let space = SweepSpace {
+            allowed_high: vec![dec("1.002"), dec("1.004")],
+            target_high: vec![dec("1.001"), dec("1.003")],
+            max_trade: vec![None, Some(Decimal::from(1_000))],
+            ..Default::default()
+        };
+        let grid = space.grid(&base());
+        // 1.003 doesn't fit under 1.002
+        assert_eq!(grid.len(), 6);
+        assert!(grid.iter().all(is_valid));
+    }
+
+    #[test]
+    fn random_search_is_reproducible_and_stays_in_range() {
+        let space = SweepSpace {
+            allowed_low: vec![dec("0.990"), dec("0.996")],
+            target_low: vec![dec("0.996"), dec("0.999")],
+            slippage_bps: vec![10, 100],
+            ..Default::default()
+        };
+        let samples = space.random(&base(), 20, 7);
+        assert_eq!(samples.len(), 20);
+        for params in &samples {
+            assert!(is_valid(params));
+            assert!((10..=100).contains(&params.slippage_bps));
+        }
+        let again = space.random(&base(), 20, 7);
+        assert_eq!(samples[0].ratio_range_allowed, again[0].ratio_range_allowed);
+    }
+
+    /**
+     * A result holding the pair at `price` throughout, with the capital and profit given.
+     */
+    fn result(
+        slippage_bps: u32,
+        price: &str,
+        capital_used: u64,
+        module_profit: i64,
+    ) -> SweepResult {
+        let params = BacktestParams {
+            slippage_bps,
+            ..base()
+        };
+        let mut report = BacktestReport::default()
+            .with_simulated_prices(&[dec(price)], params.ratio_range_allowed);
+        report.peak_module_debt = Decimal::from(capital_used);
+        report.module_profit = Decimal::from(module_profit);
+        SweepResult {
+            params,
+            report,
+            dominated_by: 0,
+        }
+    }
+
+    #[test]
+    fn results_are_ranked_by_what_dominates_them() {
+        let ranked = rank(vec![
+            // Beaten by the next one on every measure
+            result(10, "1.002", 200, 5),
+            result(20, "1.001", 100, 10),
+            // Closer to peg than the one above, but needs more capital
+            result(30, "1.0005", 300, 10),
+        ]);
+
+        let ranking: Vec<(u32, usize)> = ranked
+            .iter()
+            .map(|result| (result.params.slippage_bps, result.dominated_by))
+            .collect();
+        assert_eq!(ranking, vec![(30, 0), (20, 0), (10, 1)]);
+        assert!(ranked[1].dominates(&ranked[2]));
+        assert!(!ranked[0].dominates(&ranked[1]));
+        assert!(!ranked[1].dominates(&ranked[0]));
+    }
+
+    #[test]
+    fn results_are_written_as_csv_in_rank_order() {
+        let mut best = result(30, "1.0005", 300, 10);
+        best.report.keeper_fees = Decimal::from(3);
+        best.report.gas_cost_eth = dec("0.001");
+        let ranked = rank(vec![result(10, "1.003", 200, 5), best]);
+        let path = std::env::temp_dir().join(format!("azos-sweep-{}.csv", std::process::id()));
+
+        write_csv(&path, &ranked, Decimal::from(2_000)).unwrap();
+
+        let csv = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "rank,dominated_by,allowed_low,allowed_high,target_low,target_high,slippage_bps,max_trade,\
+             mean_deviation,max_deviation,blocks_off_peg,expansions,contractions,reverted,minted,burned,\
+             capital_used,module_profit,keeper_profit"
+        );
+        // Keeper profit is its fees less 0.001 ETH of gas at 2000
+        assert_eq!(
+            lines[1],
+            "1,0,0.996,1.002,0.997,1.001,30,,0.0005,0.0005,0,0,0,0,0,0,300,10,1.000"
+        );
+        // Off peg for its one block, with no trade cap left empty
+        assert!(lines[2].starts_with("2,0,0.996,1.002,0.997,1.001,10,,0.003,0.003,1,"));
+        assert_eq!(lines.len(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{bail, Result};
+use opentelemetry::trace::{TraceError, TracerProvider as _};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+use opentelemetry_sdk::trace::{self as sdk_trace, Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
+use serde_json::{json, Map, Value};
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::{env, fmt, io};
+
+const SERVICE_NAME: &str = "azos-keeper";
+
+/// Where spans are exported to, when anywhere
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceExport {
+    Otlp { endpoint: String },
+    Stdout,
+}
+
+impl TraceExport {
+    /**
+     * Reads the standard `OTEL_TRACES_EXPORTER` and `OTEL_EXPORTER_OTLP_ENDPOINT`.  Spans go to the collector once
+     * an endpoint is set, or to stdout when `OTEL_TRACES_EXPORTER=stdout`, and aren't exported otherwise.
+     */
+    pub fn from_env() -> Result<Option<Self>> {
+        let exporter = env::var("OTEL_TRACES_EXPORTER").unwrap_or_default();
+        let export = match exporter.as_str() {
+            "" | "otlp" => env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .ok()
+                .map(|endpoint| TraceExport::Otlp { endpoint }),
+            "stdout" => Some(TraceExport::Stdout),
+            "none" => None,
+            other => bail!("OTEL_TRACES_EXPORTER must be otlp, stdout or none, not {other}"),
+        };
+        Ok(export)
+    }
+}
+
+/// Writes every span as a line of JSON as soon as it ends, for runs without a collector
+pub struct StdoutExporter<W> {
+    writer: W,
+}
+
+impl<W> StdoutExporter<W> {
+    pub fn new(writer: W) -> Self {
+        StdoutExporter { writer }
+    }
+}
+
+impl<W> fmt::Debug for StdoutExporter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("StdoutExporter")
+    }
+}
+
+/**
+ * The fields of a span worth reading when looking for where time went.
+ */
+fn span_json(span: &SpanData) -> Value {
+    let attributes: Map<String, Value> = span
+        .attributes
+        .iter()
+        .map(|attribute| {
+            (
+                attribute.key.to_string(),
+                json!(attribute.value.to_string()),
+            )
+        })
+        .collect();
+    let duration = span
+        .end_time
+        .duration_since(span.start_time)
+        .unwrap_or_default();
+    json!({
+        "name": span.name,
+        "trace_id": span.span_context.trace_id().to_string(),
+        "span_id": span.span_context.span_id().to_string(),
+        "parent_span_id": span.parent_span_id.to_string(),
+        "duration_us": duration.as_micros() as u64,
+        "attributes": attributes,
+    })
+}
+
+impl<W: io::Write + Send + Sync> SpanExporter for StdoutExporter<W> {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        let result = batch
+            .iter()
+            .try_for_each(|span| writeln!(self.writer, "{}", span_json(span)))
+            .map_err(|error| TraceError::from(error.to_string()));
+        Box::pin(future::ready(result))
+    }
+}
+
+/**
+ * Exports spans as `export` asks, batched in the background for a collector.  Dropping the provider flushes
+ * whatever is left.
+ */
+pub fn tracer_provider(export: &TraceExport) -> Result<TracerProvider> {
+    let builder = TracerProvider::builder().with_config(
+        sdk_trace::config()
+            .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)])),
+    );
+    let builder = match export {
+        TraceExport::Otlp { endpoint } => {
+            let exporter = opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint)
+                .build_span_exporter()?;
+            builder.with_batch_exporter(exporter, runtime::Tokio)
+        }
+        TraceExport::Stdout => builder.with_simple_exporter(StdoutExporter::new(io::stdout())),
+    };
+    Ok(builder.build())
+}
+
+pub fn tracer(tracer_provider: &TracerProvider) -> Tracer {
+    tracer_provider.tracer(SERVICE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::{subscriber, LogFormat};
+    use crate::rpc::{self, FailoverClient};
+    use crate::test_utils::{mock_http_server, mock_http_server_for, Lines};
+    use ethers::providers::{JsonRpcClient, Middleware};
+    use ethers::types::U64;
+
+    #[tokio::test]
+    async fn provider_calls_are_exported_as_spans() {
+        let (url, _handle) =
+            mock_http_server(json!({"jsonrpc": "2.0", "id": 0, "result": "0x1a"})).await;
+        let client = FailoverClient::new(std::slice::from_ref(&url)).unwrap();
+        let spans = Lines::default();
+        let tracer_provider = TracerProvider::builder()
+            .with_simple_exporter(StdoutExporter::new(spans.clone()))
+            .build();
+        let subscriber = subscriber(
+            LogFormat::Text,
+            "off",
+            io::sink,
+            Some(tracer(&tracer_provider)),
+        );
+
+        {
+            let _default = tracing::subscriber::set_default(subscriber);
+            let _: U64 = client.request("eth_blockNumber", ()).await.unwrap();
+        }
+        tracer_provider.force_flush();
+
+        let output = spans.text();
+        let span: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(span["name"], "rpc");
+        assert_eq!(span["attributes"]["method"], "eth_blockNumber");
+        assert_eq!(span["attributes"]["endpoint"], url);
+    }
+
+    #[tokio::test]
+    async fn quorum_reads_are_exported_as_spans() {
+        let response = json!({"jsonrpc": "2.0", "id": 0, "result": "0x1a"});
+        let (first, _first_handle) = mock_http_server_for(response.clone(), 1).await;
+        let (second, _second_handle) = mock_http_server_for(response, 1).await;
+        let reader = rpc::quorum_reader(&[first, second], 2).unwrap();
+        let spans = Lines::default();
+        let tracer_provider = TracerProvider::builder()
+            .with_simple_exporter(StdoutExporter::new(spans.clone()))
+            .build();
+        let subscriber = subscriber(
+            LogFormat::Text,
+            "off",
+            io::sink,
+            Some(tracer(&tracer_provider)),
+        );
+
+        {
+            let _default = tracing::subscriber::set_default(subscriber);
+            assert_eq!(reader.get_block_number().await.unwrap(), U64::from(26));
+        }
+        tracer_provider.force_flush();
+
+        let output = spans.text();
+        let span: Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+        assert_eq!(span["name"], "rpc");
+        assert_eq!(span["attributes"]["method"], "eth_blockNumber");
+        assert_eq!(span["attributes"]["endpoint"], "quorum");
+        assert_eq!(span["attributes"]["quorum"], "2");
+    }
+}
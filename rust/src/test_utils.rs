@@ -0,0 +1,391 @@
+use crate::config::{AdapterConfig, AdapterKind, Config, SignerConfig};
+use crate::contracts::azos_stability_module::{
+    DebtCeilingCall, GetAdapterCall, GetDebtCall, MaxDepositCall,
+};
+use crate::contracts::uniswap_v2_factory::GetPairCall;
+use crate::contracts::uniswap_v2_pair::{GetReservesCall, GetReservesReturn, Token0Call};
+use crate::rpc::LOG_CHUNK_BLOCKS;
+use crate::types::token::{Token, TokenPair};
+use crate::types::venue::{PairVenue, Venue, VenueSelection};
+use async_trait::async_trait;
+use ethers::abi::AbiEncode;
+use ethers::contract::EthCall;
+use ethers::providers::{JsonRpcClient, JsonRpcError, MockError, Provider};
+use ethers::types::{Address, Block, Bytes, Log, H256, I256, U256, U64};
+use ethers::utils::parse_units;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+async fn read_request(socket: &mut TcpStream) -> String {
+    let mut request = vec![];
+    let mut buffer = [0u8; 4096];
+    loop {
+        let read = socket.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+            let content_length = headers
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if body.len() >= content_length || read == 0 {
+                return text;
+            }
+        }
+    }
+}
+
+/**
+ * An HTTP server that answers a single request with `response`, handing back the raw request it received.
+ */
+pub async fn mock_http_server(response: Value) -> (String, JoinHandle<String>) {
+    let (url, handle) = mock_http_server_for(response, 1).await;
+    let handle = tokio::spawn(async move { handle.await.unwrap().remove(0) });
+    (url, handle)
+}
+
+/// A log or span writer capturing everything written to it
+#[derive(Clone, Default)]
+pub struct Lines(Arc<Mutex<Vec<u8>>>);
+
+impl Lines {
+    pub fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/**
+ * An HTTP server that answers the next `requests` requests with `response`, handing back the raw requests it received
+ * in order.  Connections are closed after every response so each request arrives on a new one.
+ */
+pub async fn mock_http_server_for(
+    response: Value,
+    requests: usize,
+) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut received = vec![];
+        for _ in 0..requests {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            received.push(read_request(&mut socket).await);
+            let body = response.to_string();
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        }
+        received
+    });
+    (url, handle)
+}
+
+/**
+ * The JSON body of a request captured by `mock_http_server`.
+ */
+pub fn request_body(request: &str) -> Value {
+    let (_, body) = request.split_once("\r\n\r\n").unwrap();
+    serde_json::from_str(body).unwrap()
+}
+
+/// A JSON-RPC client that answers `eth_call` from scripted contract views, without a chain behind it.  Blocks and
+/// their logs can be mined onto it for code following the chain.
+#[derive(Debug, Default)]
+pub struct MockChain {
+    calls: Mutex<HashMap<(Address, Bytes), Bytes>>, // Keyed by the exact calldata
+    views: Mutex<HashMap<(Address, [u8; 4]), Bytes>>, // Keyed by selector, for any arguments
+    blocks: Mutex<Vec<(H256, Vec<Log>)>>,           // The canonical chain from block 0
+}
+
+impl MockChain {
+    pub fn provider(self: &Arc<Self>) -> Arc<Provider<Arc<MockChain>>> {
+        Arc::new(Provider::new(self.clone()))
+    }
+
+    /**
+     * Answers `call` made to `to` with `output`, only for exactly these arguments.
+     */
+    pub fn on_call<C: EthCall>(&self, to: Address, call: C, output: impl AbiEncode) {
+        self.calls
+            .lock()
+            .unwrap()
+            .insert((to, call.encode().into()), output.encode().into());
+    }
+
+    /**
+     * Answers every `C` made to `to` that isn't scripted with `on_call`.
+     */
+    pub fn on_any_call<C: EthCall>(&self, to: Address, output: impl AbiEncode) {
+        self.views
+            .lock()
+            .unwrap()
+            .insert((to, C::selector()), output.encode().into());
+    }
+
+    /**
+     * Appends a block with `hash`, filling in where each of its logs landed.  Returns the block's number.
+     */
+    pub fn mine(&self, hash: H256, logs: Vec<Log>) -> u64 {
+        let mut blocks = self.blocks.lock().unwrap();
+        let block_number = blocks.len() as u64;
+        let logs = logs
+            .into_iter()
+            .enumerate()
+            .map(|(index, log)| Log {
+                block_number: Some(U64::from(block_number)),
+                block_hash: Some(hash),
+                transaction_hash: Some(H256::from_low_u64_be(block_number * 1_000 + index as u64)),
+                transaction_index: Some(U64::from(index)),
+                log_index: Some(index.into()),
+                removed: Some(false),
+                ..log
+            })
+            .collect();
+        blocks.push((hash, logs));
+        block_number
+    }
+
+    /**
+     * Drops the latest `depth` blocks, for new ones to be mined in their place.
+     */
+    pub fn reorg(&self, depth: usize) {
+        let mut blocks = self.blocks.lock().unwrap();
+        let keep = blocks.len() - depth;
+        blocks.truncate(keep);
+    }
+
+    fn block_number(&self, tag: &Value) -> Option<u64> {
+        match tag.as_str()? {
+            "latest" => Some(self.blocks.lock().unwrap().len().checked_sub(1)? as u64),
+            number => u64::from_str_radix(number.trim_start_matches("0x"), 16).ok(),
+        }
+    }
+
+    fn eth_get_block_by_number(&self, params: &Value) -> Value {
+        let number = self.block_number(&params[0]);
+        let blocks = self.blocks.lock().unwrap();
+        let found = number.and_then(|number| Some((number, blocks.get(number as usize)?)));
+        match found {
+            Some((number, (hash, _))) => json!(Block::<H256> {
+                hash: Some(*hash),
+                number: Some(U64::from(number)),
+                ..Default::default()
+            }),
+            None => Value::Null,
+        }
+    }
+
+    fn eth_get_logs(&self, params: &Value) -> Option<Vec<Log>> {
+        let filter = &params[0];
+        let from_block = self.block_number(&filter["fromBlock"])?;
+        let to_block = self.block_number(&filter["toBlock"])?;
+        // Like most nodes, refuse ranges wider than we ask for at once
+        if to_block.saturating_sub(from_block) >= LOG_CHUNK_BLOCKS {
+            return None;
+        }
+        let addresses: Vec<Address> = serde_json::from_value(filter["address"].clone())
+            .or_else(|_| {
+                serde_json::from_value(filter["address"].clone()).map(|address| vec![address])
+            })
+            .unwrap_or_default();
+        let blocks = self.blocks.lock().unwrap();
+        Some(
+            blocks
+                .iter()
+                .flat_map(|(_, logs)| logs)
+                .filter(|log| {
+                    let block_number = log.block_number.unwrap().as_u64();
+                    (from_block..=to_block).contains(&block_number)
+                        && (addresses.is_empty() || addresses.contains(&log.address))
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+
+    fn eth_call(&self, params: &Value) -> Option<Bytes> {
+        let tx = &params[0];
+        let to: Address = serde_json::from_value(tx["to"].clone()).ok()?;
+        let data: Bytes = serde_json::from_value(tx["data"].clone())
+            .or_else(|_| serde_json::from_value(tx["input"].clone()))
+            .ok()?;
+        if let Some(output) = self.calls.lock().unwrap().get(&(to, data.clone())) {
+            return Some(output.clone());
+        }
+        let selector: [u8; 4] = data.get(..4)?.try_into().ok()?;
+        self.views.lock().unwrap().get(&(to, selector)).cloned()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for MockChain {
+    type Error = MockError;
+
+    async fn request<T: Serialize + Send + Sync + Debug, R: DeserializeOwned + Send>(
+        &self,
+        method: &str,
+        params: T,
+    ) -> Result<R, Self::Error> {
+        let params = serde_json::to_value(params)?;
+        let result = match method {
+            "eth_call" => self.eth_call(&params).map(|output| json!(output)),
+            "eth_blockNumber" => self
+                .block_number(&json!("latest"))
+                .map(|number| json!(U64::from(number))),
+            "eth_getBlockByNumber" => Some(self.eth_get_block_by_number(&params)),
+            "eth_getLogs" => self.eth_get_logs(&params).map(|logs| json!(logs)),
+            _ => None,
+        };
+        let result = result.ok_or_else(|| {
+            MockError::JsonRpcError(JsonRpcError {
+                code: 3,
+                message: format!("execution reverted, nothing scripted for {method} {params}"),
+                data: None,
+            })
+        })?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+/**
+ * A USDC/ZAI pair on a single Uniswap V2 venue, every contract at a recognisable address.
+ */
+pub fn test_config() -> Config {
+    let usdc = Token {
+        symbol: String::from("USDC"),
+        address: Address::repeat_byte(0x01),
+        decimals: 18,
+    };
+    let zai = Token {
+        symbol: String::from("ZAI"),
+        address: Address::repeat_byte(0x02),
+        decimals: 18,
+    };
+    Config {
+        rpc_urls: vec![String::from("http://localhost:8545")],
+        rpc_quorum: None,
+        delay_between_checks_ms: 10,
+        token_pairs: vec![TokenPair {
+            symbol: String::from("USDC/ZAI"),
+            token_0: usdc,
+            token_1: zai,
+            venues: vec![PairVenue {
+                venue: Venue::UniswapV2,
+                adapter_name: String::from("USDC"),
+            }],
+            slippage_bps: 50,
+        }],
+        keeper_signer: SignerConfig::PrivateKey(String::from(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )),
+        uniswap_router_address: Address::repeat_byte(0x10),
+        uniswap_factory_address: Address::repeat_byte(0x11),
+        uniswap_fee_rate: Decimal::from_str_exact("0.003").unwrap(),
+        stability_module_address: Address::repeat_byte(0x20),
+        ratio_range_allowed: (
+            Decimal::from_str_exact("0.996").unwrap(),
+            Decimal::from_str_exact("1.002").unwrap(),
+        ),
+        ratio_range_targets: (
+            Decimal::from_str_exact("0.997").unwrap(),
+            Decimal::from_str_exact("1.001").unwrap(),
+        ),
+        adapters: vec![AdapterConfig {
+            name: String::from("USDC"),
+            address: Address::repeat_byte(0x30),
+            kind: AdapterKind::UniswapV2 {
+                router_address: Address::repeat_byte(0x10),
+            },
+        }],
+        tx_confirmations_required: 1,
+        uniswap_v3: None,
+        routing_tokens: vec![],
+        route_discovery_max_pairs: 0,
+        venue_selection: VenueSelection::Best,
+        swap_deadline_seconds: 120,
+        max_clock_skew_seconds: 30,
+        authorization_required: false,
+        bundle_relay: None,
+        alerts: None,
+    }
+}
+
+/// The Uniswap V2 pair `mock_chain` scripts
+pub const PAIR: Address = Address::repeat_byte(0x40);
+
+/**
+ * `amount` tokens in the smallest units of a token with `decimals`.
+ */
+pub fn units(amount: &str, decimals: u64) -> U256 {
+    parse_units(amount, decimals as u32).unwrap().into()
+}
+
+/**
+ * Scripts a chain holding a single USDC/ZAI pair with the given reserves, and the adapter registered.
+ */
+pub fn mock_chain(
+    config: &Config,
+    reserve_usdc: U256,
+    reserve_zai: U256,
+    usdc_is_token_0: bool,
+) -> Arc<MockChain> {
+    let chain = Arc::new(MockChain::default());
+    let token_pair = &config.token_pairs[0];
+    let (usdc, zai) = (token_pair.token_0.address, token_pair.token_1.address);
+
+    chain.on_any_call::<GetPairCall>(config.uniswap_factory_address, Address::zero());
+    chain.on_call(
+        config.uniswap_factory_address,
+        GetPairCall {
+            token_a: usdc,
+            token_b: zai,
+        },
+        PAIR,
+    );
+    chain
+        .on_any_call::<GetAdapterCall>(config.stability_module_address, config.adapters[0].address);
+    chain.on_any_call::<DebtCeilingCall>(config.stability_module_address, U256::MAX >> 1);
+    chain.on_any_call::<MaxDepositCall>(config.stability_module_address, U256::MAX);
+    chain.on_any_call::<GetDebtCall>(config.stability_module_address, I256::zero());
+
+    let (token_0, reserve_0, reserve_1) = if usdc_is_token_0 {
+        (usdc, reserve_usdc, reserve_zai)
+    } else {
+        (zai, reserve_zai, reserve_usdc)
+    };
+    chain.on_any_call::<Token0Call>(PAIR, token_0);
+    chain.on_any_call::<GetReservesCall>(
+        PAIR,
+        GetReservesReturn {
+            reserve_0: reserve_0.as_u128(),
+            reserve_1: reserve_1.as_u128(),
+            block_timestamp_last: 0,
+        },
+    );
+    chain
+}
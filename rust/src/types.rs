@@ -1,3 +1,5 @@
 pub mod keeper;
+pub mod provider;
 pub mod swap;
 pub mod token;
+pub mod venue;
@@ -1,4 +1,5 @@
 use super::swap::SwapDetails;
+use rust_decimal::Decimal;
 
 #[derive(Clone)]
 pub enum KeeperAction {
@@ -6,3 +7,161 @@ pub enum KeeperAction {
     ContractAndSell(SwapDetails),
     None(SwapDetails),
 }
+
+impl KeeperAction {
+    pub fn swap_details(&self) -> &SwapDetails {
+        match self {
+            KeeperAction::ExpandAndBuy(swap_details)
+            | KeeperAction::ContractAndSell(swap_details)
+            | KeeperAction::None(swap_details) => swap_details,
+        }
+    }
+
+    /**
+     * The same action with its amounts scaled by `share`.
+     */
+    pub fn scaled(self, share: Decimal) -> Self {
+        let scale = |swap_details: SwapDetails| SwapDetails {
+            amount_to_sell: swap_details.amount_to_sell * share,
+            amount_to_buy_min: swap_details.amount_to_buy_min * share,
+            ..swap_details
+        };
+        match self {
+            KeeperAction::ExpandAndBuy(swap_details) => {
+                KeeperAction::ExpandAndBuy(scale(swap_details))
+            }
+            KeeperAction::ContractAndSell(swap_details) => {
+                KeeperAction::ContractAndSell(scale(swap_details))
+            }
+            KeeperAction::None(swap_details) => KeeperAction::None(swap_details),
+        }
+    }
+}
+
+/**
+ * Splits the actions quoted across a pair's venues into expansions and contractions, dropping those needing none.
+ * Venues can disagree on direction, so each side is selected from independently.
+ */
+pub fn by_direction(actions: Vec<KeeperAction>) -> [Vec<KeeperAction>; 2] {
+    let (expansions, contractions): (Vec<KeeperAction>, Vec<KeeperAction>) = actions
+        .into_iter()
+        .filter(|action| !matches!(action, KeeperAction::None(_)))
+        .partition(|action| matches!(action, KeeperAction::ExpandAndBuy(_)));
+    [expansions, contractions]
+}
+
+/**
+ * The amount the venues on one side are compared at, the smallest of their corrections, so none is quoted past its
+ * target.
+ */
+pub fn common_input_size(actions: &[KeeperAction]) -> Option<Decimal> {
+    actions
+        .iter()
+        .map(|action| action.swap_details().amount_to_sell)
+        .min()
+}
+
+/**
+ * The action on the venue giving the most for the common input size, `outputs` being what each venue quotes for it.
+ */
+pub fn best_action(actions: Vec<KeeperAction>, outputs: &[Decimal]) -> Option<KeeperAction> {
+    actions
+        .into_iter()
+        .zip(outputs)
+        .max_by(|(_, a), (_, b)| a.cmp(b))
+        .map(|(action, _)| action)
+}
+
+/**
+ * Divides one correction, the largest of the side's, across its venues in proportion to each one's own.  Minimums
+ * shrink with the amounts, which is conservative as smaller swaps fill at better rates.
+ */
+pub fn split_correction(actions: Vec<KeeperAction>) -> Vec<KeeperAction> {
+    let amounts = actions
+        .iter()
+        .map(|action| action.swap_details().amount_to_sell);
+    let (Some(largest), total) = (amounts.clone().max(), amounts.sum::<Decimal>()) else {
+        return vec![];
+    };
+    let share = largest / total;
+    actions
+        .into_iter()
+        .map(|action| action.scaled(share))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::token::Token;
+    use crate::types::venue::Venue;
+    use ethers::types::Address;
+
+    fn swap_details(venue: Venue, amount_to_sell: u64, amount_to_buy_min: u64) -> SwapDetails {
+        let token = Token {
+            symbol: String::from("ZAI"),
+            address: Address::zero(),
+            decimals: 18,
+        };
+        SwapDetails {
+            dex_price: Decimal::ONE,
+            venue,
+            adapter_name: String::from("USDC"),
+            token_to_sell: token.clone(),
+            amount_to_sell: Decimal::from(amount_to_sell),
+            token_to_buy: token,
+            amount_to_buy_min: Decimal::from(amount_to_buy_min),
+            path: vec![],
+        }
+    }
+
+    fn actions() -> Vec<KeeperAction> {
+        vec![
+            // The deeper correction fills at a worse rate for its size alone
+            KeeperAction::ExpandAndBuy(swap_details(Venue::UniswapV2, 300, 288)),
+            KeeperAction::ExpandAndBuy(swap_details(Venue::UniswapV3 { fee: 500 }, 100, 97)),
+            KeeperAction::None(swap_details(Venue::UniswapV3 { fee: 100 }, 0, 0)),
+            KeeperAction::ContractAndSell(swap_details(Venue::UniswapV2, 50, 49)),
+        ]
+    }
+
+    #[test]
+    fn directions_are_selected_apart() {
+        let [expansions, contractions] = by_direction(actions());
+        assert_eq!(expansions.len(), 2);
+        assert_eq!(contractions.len(), 1);
+    }
+
+    #[test]
+    fn best_compares_venues_at_the_same_size() {
+        let [expansions, _] = by_direction(actions());
+        assert_eq!(common_input_size(&expansions), Some(Decimal::from(100)));
+
+        // For 100 each, Uniswap V2 gives more, though its own correction's rate is lower
+        let outputs = [Decimal::from(98), Decimal::from(97)];
+        let best = best_action(expansions, &outputs).unwrap();
+        assert!(matches!(best.swap_details().venue, Venue::UniswapV2));
+        assert_eq!(best.swap_details().amount_to_sell, Decimal::from(300));
+    }
+
+    #[test]
+    fn split_divides_one_correction() {
+        let [expansions, _] = by_direction(actions());
+        let split = split_correction(expansions);
+        let amounts: Vec<(Decimal, Decimal)> = split
+            .iter()
+            .map(|action| {
+                let swap_details = action.swap_details();
+                (swap_details.amount_to_sell, swap_details.amount_to_buy_min)
+            })
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                (Decimal::from(225), Decimal::from(216)),
+                (Decimal::from(75), Decimal::from_str_exact("72.75").unwrap()),
+            ]
+        );
+        assert!(split_correction(vec![]).is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+use crate::contracts::azos_stability_module::AzosStabilityModule;
+use crate::contracts::uniswap_v2_factory::UniswapV2Factory;
+use crate::contracts::uniswap_v2_router02::UniswapV2Router02;
+use crate::contracts::uniswap_v3_factory::UniswapV3Factory;
+use crate::contracts::uniswap_v3_quoter_v2::UniswapV3QuoterV2;
+use crate::rpc::FailoverClient;
+use crate::signer::KeeperSigner;
+use ethers::prelude::*;
+
+pub type KeeperProvider = SignerMiddleware<Provider<FailoverClient>, KeeperSigner>;
+pub type UniswapRouter<M = KeeperProvider> = UniswapV2Router02<M>;
+pub type UniswapFactory<M = KeeperProvider> = UniswapV2Factory<M>;
+pub type UniswapV3PoolFactory<M = KeeperProvider> = UniswapV3Factory<M>;
+pub type UniswapV3Quoter<M = KeeperProvider> = UniswapV3QuoterV2<M>;
+pub type StabilityModule<M = KeeperProvider> = AzosStabilityModule<M>;
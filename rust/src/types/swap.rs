@@ -2,10 +2,13 @@ use ethers::types::Address;
 use rust_decimal::Decimal;
 
 use super::token::Token;
+use super::venue::Venue;
 
 #[derive(Clone)]
 pub struct SwapDetails {
     pub dex_price: Decimal,
+    pub venue: Venue,
+    pub adapter_name: String,
     pub token_to_sell: Token,
     pub amount_to_sell: Decimal,
     pub token_to_buy: Token,
@@ -1,3 +1,4 @@
+use super::venue::PairVenue;
 use ethers::abi::Address;
 
 #[derive(Clone)]
@@ -12,4 +13,6 @@ pub struct TokenPair {
     pub symbol: String,
     pub token_0: Token,
     pub token_1: Token,
+    pub venues: Vec<PairVenue>,
+    pub slippage_bps: u32, // How far below the quoted purchase a swap may land
 }
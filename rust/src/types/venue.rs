@@ -0,0 +1,31 @@
+use ethers::types::Address;
+
+/// The kind of pool a token pair is traded on, which decides how it is priced and how the swap is encoded
+#[derive(Clone, Debug)]
+pub enum Venue {
+    UniswapV2,
+    UniswapV3 {
+        fee: u32,
+    },
+    /// A plain two coin Curve pool, `coins` in the pool's own index order
+    CurveStableSwap {
+        pool: Address,
+        coins: [Address; 2],
+    },
+}
+
+/// A venue a pair trades on, and the adapter the stability module swaps through there
+#[derive(Clone, Debug)]
+pub struct PairVenue {
+    pub venue: Venue,
+    pub adapter_name: String,
+}
+
+/// How to act when a pair is out of range on more than one venue
+#[derive(Clone, Copy, Debug)]
+pub enum VenueSelection {
+    /// Only correct the venue giving the most for the same amount sold
+    Best,
+    /// Divide one correction across every venue that's out of range, one call per adapter
+    Split,
+}
@@ -1,2 +1,5 @@
+pub mod constant_product;
 pub mod decimal;
+pub mod stableswap;
 pub mod time;
+pub mod uniswap_v3;
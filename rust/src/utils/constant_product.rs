@@ -0,0 +1,157 @@
+use rust_decimal::Decimal;
+
+// Bisection steps when sizing against a route, plenty to converge within Decimal precision
+const MAX_ITERATIONS: usize = 128;
+
+/// Reserves of one hop of a route, oriented in the direction of the swap
+#[derive(Clone, Copy, Debug)]
+pub struct Hop {
+    pub reserve_in: Decimal,
+    pub reserve_out: Decimal,
+}
+
+/**
+ * Uniswap V2's `getAmountOut`, without the integer rounding.
+ */
+pub fn get_amount_out(amount_in: Decimal, hop: &Hop, fee_rate: Decimal) -> Decimal {
+    let amount_in_with_fee = amount_in * (Decimal::ONE - fee_rate);
+    amount_in_with_fee * hop.reserve_out / (hop.reserve_in + amount_in_with_fee)
+}
+
+/**
+ * Uniswap V2's `getAmountIn`, without the integer rounding.
+ */
+pub fn get_amount_in(amount_out: Decimal, hop: &Hop, fee_rate: Decimal) -> Decimal {
+    hop.reserve_in * amount_out / ((hop.reserve_out - amount_out) * (Decimal::ONE - fee_rate))
+}
+
+/**
+ * Marginal rate of the route ignoring fees, how much comes out at the end per unit put in at the start.
+ */
+pub fn route_price(hops: &[Hop]) -> Decimal {
+    hops.iter().fold(Decimal::ONE, |price, hop| {
+        price * hop.reserve_out / hop.reserve_in
+    })
+}
+
+/**
+ * Swaps `amount_in` through every hop, returning the amount out and the reserves afterwards.
+ */
+pub fn simulate_route(amount_in: Decimal, hops: &[Hop], fee_rate: Decimal) -> (Decimal, Vec<Hop>) {
+    let mut amount = amount_in;
+    let mut hops_after = Vec::with_capacity(hops.len());
+    for hop in hops {
+        let amount_out = get_amount_out(amount, hop, fee_rate);
+        hops_after.push(Hop {
+            reserve_in: hop.reserve_in + amount,
+            reserve_out: hop.reserve_out - amount_out,
+        });
+        amount = amount_out;
+    }
+    (amount, hops_after)
+}
+
+/**
+ * How much to put into the route so its marginal rate falls to `target_price`.  Zero if it's already there.
+ */
+pub fn amount_in_to_reach_price(hops: &[Hop], fee_rate: Decimal, target_price: Decimal) -> Decimal {
+    if route_price(hops) <= target_price {
+        return Decimal::ZERO;
+    }
+
+    // Putting in more only ever lowers the rate, so bisect between nothing and the first hop's whole reserve
+    let mut low = Decimal::ZERO;
+    let mut high = hops[0].reserve_in;
+    while route_price(&simulate_route(high, hops, fee_rate).1) > target_price {
+        high *= Decimal::TWO;
+    }
+    for _ in 0..MAX_ITERATIONS {
+        let mid = (low + high) / Decimal::TWO;
+        if mid == low || mid == high {
+            break;
+        }
+        if route_price(&simulate_route(mid, hops, fee_rate).1) > target_price {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    high
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str_exact(value).unwrap()
+    }
+
+    #[test]
+    fn route_price_multiplies_each_hop() {
+        let hops = [
+            Hop {
+                reserve_in: dec("1000"),
+                reserve_out: dec("500"),
+            },
+            Hop {
+                reserve_in: dec("100"),
+                reserve_out: dec("400"),
+            },
+        ];
+        assert_eq!(route_price(&hops), dec("2"));
+    }
+
+    #[test]
+    fn amount_out_matches_uniswap_formula() {
+        let hop = Hop {
+            reserve_in: dec("1000"),
+            reserve_out: dec("1000"),
+        };
+        // 997 * 1000 / (1000 + 997)
+        let amount_out = get_amount_out(dec("100"), &hop, dec("0.003"));
+        assert_eq!(amount_out.round_dp(6), dec("90.661089"));
+    }
+
+    #[test]
+    fn amount_in_inverts_amount_out() {
+        let hop = Hop {
+            reserve_in: dec("1000"),
+            reserve_out: dec("1000"),
+        };
+        let amount_in = get_amount_in(dec("90.661089"), &hop, dec("0.003"));
+        assert_eq!(amount_in.round_dp(4), dec("100.0000"));
+    }
+
+    #[test]
+    fn sizing_moves_a_two_hop_route_to_the_target() {
+        let hops = [
+            Hop {
+                reserve_in: dec("1000000"),
+                reserve_out: dec("500"),
+            },
+            Hop {
+                reserve_in: dec("480"),
+                reserve_out: dec("1000000"),
+            },
+        ];
+        let target = dec("1.001");
+        assert!(route_price(&hops) > target);
+
+        let amount_in = amount_in_to_reach_price(&hops, dec("0.003"), target);
+        let (_, hops_after) = simulate_route(amount_in, &hops, dec("0.003"));
+        assert!((route_price(&hops_after) - target).abs() < dec("0.000001"));
+    }
+
+    #[test]
+    fn no_trade_needed_below_target() {
+        let hops = [Hop {
+            reserve_in: dec("1000"),
+            reserve_out: dec("990"),
+        }];
+        assert_eq!(
+            amount_in_to_reach_price(&hops, dec("0.003"), dec("1.001")),
+            Decimal::ZERO
+        );
+    }
+}
@@ -1,10 +1,12 @@
-use ethers::types::U256;
-use log::debug;
+use ethers::types::{I256, U256};
 use rust_decimal::{Decimal, MathematicalOps};
+use tracing::debug;
+
+pub const BPS_DENOMINATOR: u32 = 10_000;
 
 pub fn decimal_to_u256(dec: Decimal, decimals: u64) -> U256 {
     let rounded = (dec * Decimal::from(10).checked_powu(decimals).unwrap()).floor();
-    debug!("decimal_to_u256, dec={dec}, decimals={decimals}, rounded={rounded}");
+    debug!(%dec, decimals, %rounded, "decimal_to_u256");
     U256::from_dec_str(rounded.to_string().as_str()).unwrap()
 }
 
@@ -14,3 +16,40 @@ pub fn decimal_to_u256(dec: Decimal, decimals: u64) -> U256 {
 pub fn decimal_is_within_allowed_range(price: Decimal, allowed_range: (Decimal, Decimal)) -> bool {
     price >= allowed_range.0 && price <= allowed_range.1
 }
+
+pub fn u256_to_decimal(value: U256, decimals: u64) -> Decimal {
+    Decimal::from(value.as_u128()) / Decimal::from(10).checked_powu(decimals).unwrap()
+}
+
+/**
+ * Signed amounts, like the module's debt, which goes negative once more has been burned than minted.
+ */
+pub fn i256_to_decimal(value: I256, decimals: u64) -> Decimal {
+    let size = u256_to_decimal(value.unsigned_abs(), decimals);
+    if value.is_negative() {
+        -size
+    } else {
+        size
+    }
+}
+
+/**
+ * Lowers an expected amount by `slippage_bps` basis points, the least we're willing to accept.
+ */
+pub fn apply_slippage_bps(amount: Decimal, slippage_bps: u32) -> Decimal {
+    amount * (Decimal::from(BPS_DENOMINATOR) - Decimal::from(slippage_bps))
+        / Decimal::from(BPS_DENOMINATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slippage_lowers_the_amount_by_basis_points() {
+        let amount = Decimal::from(1_000);
+        assert_eq!(apply_slippage_bps(amount, 0), amount);
+        assert_eq!(apply_slippage_bps(amount, 50), Decimal::from(995));
+        assert_eq!(apply_slippage_bps(amount, 10_000), Decimal::ZERO);
+    }
+}
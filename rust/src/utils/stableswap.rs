@@ -0,0 +1,338 @@
+use anyhow::{anyhow, bail, Result};
+use ethers::types::U256;
+use rust_decimal::Decimal;
+
+// Curve StableSwap math, ported from the plain pool Vyper implementation so results match on-chain to the wei.
+//
+// Balances (`xp`) are normalized to 18 decimals, `amp` is A * A_PRECISION (as returned by `A_precise()`, or
+// `A() * 100` on older pools), and fees use Curve's 1e10 denominator.
+pub const A_PRECISION: u64 = 100;
+pub const FEE_DENOMINATOR: u64 = 10_000_000_000;
+
+const MAX_ITERATIONS: usize = 255;
+const PRICE_PRECISION: usize = 18;
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+fn empty_balance() -> anyhow::Error {
+    anyhow!("StableSwap math on an empty pool balance")
+}
+
+fn d_product(xp: &[U256], d: U256) -> Result<U256> {
+    let n_coins = U256::from(xp.len());
+    xp.iter().try_fold(d, |d_product, x| {
+        (d_product * d)
+            .checked_div(*x * n_coins)
+            .ok_or_else(empty_balance)
+    })
+}
+
+/**
+ * The StableSwap invariant D for the given balances.
+ */
+pub fn get_d(xp: &[U256], amp: U256) -> Result<U256> {
+    let n_coins = U256::from(xp.len());
+    let a_precision = U256::from(A_PRECISION);
+    let sum: U256 = xp.iter().fold(U256::zero(), |sum, x| sum + x);
+    if sum.is_zero() {
+        return Ok(U256::zero());
+    }
+
+    let ann = amp * n_coins;
+    let ann_less_precision = ann
+        .checked_sub(a_precision)
+        .ok_or_else(|| anyhow!("StableSwap amplification of {amp} is too low"))?;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let d_p = d_product(xp, d)?;
+        let d_prev = d;
+        d = ((ann * sum / a_precision + d_p * n_coins) * d)
+            .checked_div(ann_less_precision * d / a_precision + (n_coins + 1) * d_p)
+            .ok_or_else(empty_balance)?;
+        if abs_diff(d, d_prev) <= U256::one() {
+            return Ok(d);
+        }
+    }
+    bail!("StableSwap D did not converge");
+}
+
+/**
+ * The balance of coin `j` that keeps D constant after coin `i` is set to `x`.
+ */
+pub fn get_y(i: usize, j: usize, x: U256, xp: &[U256], amp: U256) -> Result<U256> {
+    let n_coins = U256::from(xp.len());
+    let a_precision = U256::from(A_PRECISION);
+    let d = get_d(xp, amp)?;
+    let ann = amp * n_coins;
+
+    let mut c = d;
+    let mut sum = U256::zero();
+    for (k, balance) in xp.iter().enumerate() {
+        let x_k = if k == i {
+            x
+        } else if k != j {
+            *balance
+        } else {
+            continue;
+        };
+        sum += x_k;
+        c = (c * d)
+            .checked_div(x_k * n_coins)
+            .ok_or_else(empty_balance)?;
+    }
+    c = c * d * a_precision / (ann * n_coins);
+    let b = sum + d * a_precision / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        y = (U256::from(2) * y + b)
+            .checked_sub(d)
+            .and_then(|denominator| (y * y + c).checked_div(denominator))
+            .ok_or_else(empty_balance)?;
+        if abs_diff(y, y_prev) <= U256::one() {
+            return Ok(y);
+        }
+    }
+    bail!("StableSwap y did not converge");
+}
+
+/**
+ * Amount of coin `j` received for selling `dx` of coin `i`, after fees.  Fails when the pool can't pay anything out.
+ */
+pub fn get_dy(i: usize, j: usize, dx: U256, xp: &[U256], amp: U256, fee: U256) -> Result<U256> {
+    let y = get_y(i, j, xp[i] + dx, xp, amp)?;
+    let dy = xp[j]
+        .checked_sub(y)
+        .and_then(|dy| dy.checked_sub(U256::one()))
+        .ok_or_else(|| anyhow!("StableSwap pool holds too little of coin {j} to pay out"))?;
+    Ok(dy - fee * dy / U256::from(FEE_DENOMINATOR))
+}
+
+/**
+ * Amount of coin `i` that must be sold to receive `dy` of coin `j`, after fees.  The inverse of `get_dy`.
+ */
+pub fn get_dx(i: usize, j: usize, dy: U256, xp: &[U256], amp: U256, fee: U256) -> Result<U256> {
+    let fee_denominator = U256::from(FEE_DENOMINATOR);
+    let dy_before_fee = fee_denominator
+        .checked_sub(fee)
+        .and_then(|denominator| (dy * fee_denominator).checked_div(denominator))
+        .ok_or_else(|| anyhow!("StableSwap fee of {fee} takes the whole swap"))?;
+    let y = xp[j]
+        .checked_sub(dy_before_fee)
+        .ok_or_else(|| anyhow!("StableSwap pool holds less than {dy_before_fee} of coin {j}"))?;
+    let x = get_y(j, i, y, xp, amp)?;
+    x.checked_sub(xp[i])
+        .ok_or_else(|| anyhow!("StableSwap balance of coin {i} fell while buying coin {j}"))
+}
+
+/**
+ * Marginal price of coin `i` in terms of coin `j` (how much `j` one unit of `i` buys), ignoring fees, scaled by 1e18.
+ */
+pub fn spot_price(i: usize, j: usize, xp: &[U256], amp: U256, d: U256) -> Result<U256> {
+    let n_coins = U256::from(xp.len());
+    let a_precision = U256::from(A_PRECISION);
+    let ann = amp * n_coins;
+    let d_p = d_product(xp, d)? * a_precision;
+    (xp[j] * (ann * xp[i] + d_p) * U256::exp10(PRICE_PRECISION))
+        .checked_div(xp[i] * (ann * xp[j] + d_p))
+        .ok_or_else(empty_balance)
+}
+
+/**
+ * How much of coin `i` to sell so the spot price of `i` in terms of `j` falls to `target_price`.  Returns zero when
+ * the price is already at or below the target.
+ */
+pub fn dx_to_reach_price(
+    i: usize,
+    j: usize,
+    xp: &[U256],
+    amp: U256,
+    target_price: Decimal,
+) -> Result<U256> {
+    let target_price = U256::from_dec_str(
+        (target_price * Decimal::from(10u64.pow(PRICE_PRECISION as u32)))
+            .floor()
+            .to_string()
+            .as_str(),
+    )?;
+    let d = get_d(xp, amp)?;
+    if spot_price(i, j, xp, amp, d)? <= target_price {
+        return Ok(U256::zero());
+    }
+
+    // Selling coin i only ever lowers its price, so bisect over the amount sold
+    let mut low = U256::zero();
+    let mut high: U256 = xp.iter().fold(U256::zero(), |sum, x| sum + x);
+    while high - low > U256::one() {
+        let mid = (low + high) / 2;
+        let mut xp_after = xp.to_vec();
+        xp_after[i] = xp[i] + mid;
+        xp_after[j] = get_y(i, j, xp_after[i], xp, amp)?;
+        if spot_price(i, j, &xp_after, amp, d)? > target_price {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(high)
+}
+
+/**
+ * Converts a 1e18 scaled price from `spot_price` into a Decimal.
+ */
+pub fn price_to_decimal(price: U256) -> Decimal {
+    Decimal::from_i128_with_scale(price.as_u128() as i128, PRICE_PRECISION as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference values come from the `Curve` model in curve-contract's `tests/simulation.py`, the pure Python
+    // StableSwap Curve's own test suite checks its pools against, built as `Curve(A, balances, n)` with A = amp / 100.
+    // That model takes A without A_PRECISION and returns dy before the `- 1` and fee of the plain pool's `get_dy`, so
+    // those two are applied on top of `Curve.y` as `StableSwap3Pool.vy` does
+    fn ether(amount: u64) -> U256 {
+        U256::from(amount) * U256::exp10(18)
+    }
+
+    fn dec(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    fn amp(a: u64) -> U256 {
+        U256::from(a * A_PRECISION)
+    }
+
+    #[test]
+    fn balanced_pool_invariant_is_the_sum_of_balances() {
+        let xp = [ether(1_000_000), ether(1_000_000)];
+        assert_eq!(get_d(&xp, amp(200)).unwrap(), ether(2_000_000));
+
+        let xp = [ether(1_000_000), ether(1_000_000), ether(1_000_000)];
+        assert_eq!(get_d(&xp, amp(2000)).unwrap(), ether(3_000_000));
+    }
+
+    #[test]
+    fn imbalanced_pool_invariant_matches_reference() {
+        // Curve(200, [1_200_000e18, 800_000e18], 2).D()
+        let xp = [ether(1_200_000), ether(800_000)];
+        assert_eq!(
+            get_d(&xp, amp(200)).unwrap(),
+            dec("1999792767421987910149588")
+        );
+
+        // Curve(50, [500_000e18, 1_500_000e18], 2).D()
+        let xp = [ether(500_000), ether(1_500_000)];
+        assert_eq!(
+            get_d(&xp, amp(50)).unwrap(),
+            dec("1993526695304897066979069")
+        );
+
+        // Curve(2000, [1_000_000e18, 1_300_000e18, 700_000e18], 3).D()
+        let xp = [ether(1_000_000), ether(1_300_000), ether(700_000)];
+        assert_eq!(
+            get_d(&xp, amp(2000)).unwrap(),
+            dec("2999950577419576626184326")
+        );
+    }
+
+    #[test]
+    fn get_y_matches_reference() {
+        // Curve(200, [1_200_000e18, 800_000e18], 2).y(0, 1, 1_210_000e18)
+        let xp = [ether(1_200_000), ether(800_000)];
+        let y = get_y(0, 1, xp[0] + ether(10_000), &xp, amp(200)).unwrap();
+        assert_eq!(y, dec("790022185544702621071669"));
+    }
+
+    #[test]
+    fn get_dy_matches_reference() {
+        // Curve(200, [1_200_000e18, 800_000e18], 2), dy = xp[j] - y(i, j, xp[i] + 10_000e18) - 1, less a 0.04% fee
+        let fee = U256::from(4_000_000);
+        let xp = [ether(1_200_000), ether(800_000)];
+        assert_eq!(
+            get_dy(0, 1, ether(10_000), &xp, amp(200), fee).unwrap(),
+            dec("9973823329515259976759")
+        );
+        assert_eq!(
+            get_dy(1, 0, ether(10_000), &xp, amp(200), fee).unwrap(),
+            dec("10016966898722164945141")
+        );
+
+        // Curve(2000, [1_000_000e18] * 3, 3), 1_000e18 of coin 0 for coin 2 less a 0.01% fee
+        let xp = [ether(1_000_000), ether(1_000_000), ether(1_000_000)];
+        assert_eq!(
+            get_dy(0, 2, ether(1_000), &xp, amp(2000), U256::from(1_000_000)).unwrap(),
+            dec("999899500299600599475")
+        );
+
+        // Curve(2000, [1_000_000e18, 1_300_000e18, 700_000e18], 3), 50_000e18 of coin 2 for coin 1 less a 0.01% fee
+        let xp = [ether(1_000_000), ether(1_300_000), ether(700_000)];
+        assert_eq!(
+            get_dy(2, 1, ether(50_000), &xp, amp(2000), U256::from(1_000_000)).unwrap(),
+            dec("50011107670213855541475")
+        );
+    }
+
+    #[test]
+    fn get_dx_matches_reference() {
+        // Curve(200, [1_200_000e18, 800_000e18], 2), dx = y(j, i, xp[j] - 10_000e18 * 1e10 / (1e10 - fee)) - xp[i]
+        let fee = U256::from(4_000_000);
+        let xp = [ether(1_200_000), ether(800_000)];
+        assert_eq!(
+            get_dx(1, 0, ether(10_000), &xp, amp(200), fee).unwrap(),
+            dec("9983060792247820343661")
+        );
+    }
+
+    #[test]
+    fn spot_price_is_one_when_balanced() {
+        let xp = [ether(1_000_000), ether(1_000_000)];
+        let d = get_d(&xp, amp(200)).unwrap();
+        assert_eq!(spot_price(0, 1, &xp, amp(200), d).unwrap(), U256::exp10(18));
+    }
+
+    #[test]
+    fn selling_to_target_price_lands_on_target() {
+        let xp = [ether(1_200_000), ether(800_000)];
+        let amp = amp(200);
+        let d = get_d(&xp, amp).unwrap();
+        let target = Decimal::from_str_exact("1.001").unwrap();
+        assert!(price_to_decimal(spot_price(1, 0, &xp, amp, d).unwrap()) > target);
+
+        let dx = dx_to_reach_price(1, 0, &xp, amp, target).unwrap();
+        let mut xp_after = xp;
+        xp_after[1] = xp[1] + dx;
+        xp_after[0] = get_y(1, 0, xp_after[1], &xp, amp).unwrap();
+        let price_after = price_to_decimal(spot_price(1, 0, &xp_after, amp, d).unwrap());
+        assert!((price_after - target).abs() < Decimal::from_str_exact("0.000001").unwrap());
+    }
+
+    #[test]
+    fn no_trade_needed_below_target() {
+        let xp = [ether(1_200_000), ether(800_000)];
+        let target = Decimal::from_str_exact("1.001").unwrap();
+        assert_eq!(
+            dx_to_reach_price(0, 1, &xp, amp(200), target).unwrap(),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn emptied_pools_are_an_error() {
+        let amp = amp(200);
+        assert!(get_d(&[ether(1_000_000), U256::zero()], amp).is_err());
+
+        // A dust balance can't pay out anything
+        let xp = [ether(1_000_000), U256::from(1)];
+        assert!(get_dy(0, 1, ether(10_000), &xp, amp, U256::zero()).is_err());
+        assert!(get_dx(0, 1, ether(1), &xp, amp, U256::zero()).is_err());
+    }
+}
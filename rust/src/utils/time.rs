@@ -1,12 +1,34 @@
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
 
 use ethers::types::U256;
 
-pub fn get_swap_deadline_from_now() -> U256 {
-    let future = SystemTime::now() + Duration::from_secs(120);
-    let future_timestamp = future
+/**
+ * Swaps must land within `offset_seconds` of the latest block, chain time is what the router checks against.
+ */
+pub fn get_swap_deadline(block_timestamp: U256, offset_seconds: u64) -> U256 {
+    block_timestamp + U256::from(offset_seconds)
+}
+
+/**
+ * How far the local clock is ahead of the chain, negative when it's behind.
+ */
+pub fn clock_skew_seconds(block_timestamp: U256) -> i64 {
+    let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    U256::from(future_timestamp)
+    now as i64 - block_timestamp.as_u64() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadline_is_offset_from_the_block() {
+        assert_eq!(
+            get_swap_deadline(U256::from(1_700_000_000u64), 120),
+            U256::from(1_700_000_120u64)
+        );
+    }
 }
@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use ethers::types::U256;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+const SQRT_PRICE_SCALE: u32 = 18;
+const TICK_BASE: f64 = 1.0001;
+
+/**
+ * Converts a Q64.96 `sqrtPriceX96` from `slot0` into a Decimal, without overflowing the Decimal mantissa.
+ */
+pub fn sqrt_price_x96_to_decimal(sqrt_price_x96: U256) -> Result<Decimal> {
+    let out_of_range = || anyhow!("sqrtPriceX96={sqrt_price_x96} is out of range for Decimal");
+    let scaled = sqrt_price_x96
+        .checked_mul(U256::exp10(SQRT_PRICE_SCALE as usize))
+        .ok_or_else(out_of_range)?
+        >> 96;
+    let scaled = i128::try_from(u128::try_from(scaled).map_err(|_| out_of_range())?)?;
+    Decimal::try_from_i128_with_scale(scaled, SQRT_PRICE_SCALE).map_err(|_| out_of_range())
+}
+
+pub fn sqrt_price_at_tick(tick: i32) -> Decimal {
+    Decimal::from_f64(TICK_BASE.powf(tick as f64 / 2.0)).unwrap()
+}
+
+/**
+ * The tick containing the given sqrt price, i.e. the greatest tick whose sqrt price is at or below it.
+ */
+pub fn tick_at_sqrt_price(sqrt_price: Decimal) -> i32 {
+    let price = (sqrt_price * sqrt_price).to_f64().unwrap();
+    let tick = (price.ln() / TICK_BASE.ln()).floor() as i32;
+    // Nudge for floating point error right on a boundary
+    if sqrt_price_at_tick(tick + 1) <= sqrt_price {
+        tick + 1
+    } else if sqrt_price_at_tick(tick) > sqrt_price {
+        tick - 1
+    } else {
+        tick
+    }
+}
+
+/**
+ * Every tick boundary (multiple of `tick_spacing`) that a swap from `current_tick` to `target_tick` crosses, in the
+ * order they're crossed.  Moving down crosses boundaries at or below the current tick, moving up crosses the ones
+ * strictly above it.
+ */
+pub fn tick_boundaries_between(current_tick: i32, target_tick: i32, tick_spacing: i32) -> Vec<i32> {
+    let compressed = current_tick.div_euclid(tick_spacing);
+    if target_tick < current_tick {
+        (0..)
+            .map(|step| (compressed - step) * tick_spacing)
+            .take_while(|boundary| *boundary > target_tick)
+            .collect()
+    } else {
+        (1..)
+            .map(|step| (compressed + step) * tick_spacing)
+            .take_while(|boundary| *boundary <= target_tick)
+            .collect()
+    }
+}
+
+/**
+ * Walks the price from `sqrt_price_current` to `sqrt_price_target`, adjusting the active liquidity at each
+ * initialized tick crossed, and returns the raw `(amount0, amount1)` moved through the pool (before fees).
+ *
+ * `initialized_ticks` are `(tick, liquidityNet)` pairs in the order they are crossed.
+ */
+pub fn amounts_to_reach_sqrt_price(
+    sqrt_price_current: Decimal,
+    sqrt_price_target: Decimal,
+    liquidity: u128,
+    initialized_ticks: &[(i32, i128)],
+) -> Result<(Decimal, Decimal)> {
+    let zero_for_one = sqrt_price_target < sqrt_price_current;
+    let mut liquidity = Decimal::from_u128(liquidity)
+        .ok_or_else(|| anyhow!("liquidity={liquidity} is out of range for Decimal"))?;
+    let mut sqrt_price = sqrt_price_current;
+    let mut amount_0 = Decimal::ZERO;
+    let mut amount_1 = Decimal::ZERO;
+
+    let mut step = |from: Decimal, to: Decimal, liquidity: Decimal| {
+        amount_0 += liquidity * (Decimal::ONE / from - Decimal::ONE / to).abs();
+        amount_1 += liquidity * (to - from).abs();
+    };
+
+    for (tick, liquidity_net) in initialized_ticks {
+        let sqrt_price_boundary = sqrt_price_at_tick(*tick);
+        let reached_target = if zero_for_one {
+            sqrt_price_boundary <= sqrt_price_target
+        } else {
+            sqrt_price_boundary >= sqrt_price_target
+        };
+        if reached_target {
+            break;
+        }
+        step(sqrt_price, sqrt_price_boundary, liquidity);
+        sqrt_price = sqrt_price_boundary;
+
+        // liquidityNet is added when crossing left to right, and subtracted when crossing right to left
+        let liquidity_net = Decimal::from_i128(*liquidity_net).ok_or_else(|| {
+            anyhow!("liquidityNet={liquidity_net} at tick={tick} is out of range for Decimal")
+        })?;
+        liquidity = if zero_for_one {
+            liquidity - liquidity_net
+        } else {
+            liquidity + liquidity_net
+        }
+        .max(Decimal::ZERO);
+    }
+    step(sqrt_price, sqrt_price_target, liquidity);
+
+    Ok((amount_0, amount_1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_prices_convert_to_decimal() {
+        let sqrt_price = sqrt_price_x96_to_decimal(U256::one() << 96).unwrap();
+        assert_eq!(sqrt_price, Decimal::ONE);
+    }
+
+    #[test]
+    fn out_of_range_pool_state_is_an_error() {
+        assert!(sqrt_price_x96_to_decimal(U256::MAX).is_err());
+        assert!(sqrt_price_x96_to_decimal(U256::one() << 160).is_err());
+
+        let result = amounts_to_reach_sqrt_price(Decimal::ONE, Decimal::TWO, u128::MAX, &[]);
+        assert!(result.is_err_and(|error| error.to_string().contains("liquidity")));
+        let result =
+            amounts_to_reach_sqrt_price(Decimal::ONE, Decimal::TWO, 1_000, &[(1, i128::MAX)]);
+        assert!(result.is_err_and(|error| error.to_string().contains("liquidityNet")));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod curve;
+pub mod uniswap_v2;
+pub mod uniswap_v3;
@@ -0,0 +1,183 @@
+use crate::config::Config;
+use crate::contracts::curve_stable_swap::CurveStableSwap;
+use crate::types::swap::SwapDetails;
+use crate::types::token::{Token, TokenPair};
+use crate::utils::decimal::{decimal_is_within_allowed_range, decimal_to_u256, u256_to_decimal};
+use crate::utils::stableswap::{
+    dx_to_reach_price, get_d, get_dx, get_dy, price_to_decimal, spot_price, A_PRECISION,
+};
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::Address;
+use ethers::providers::Middleware;
+use ethers::types::U256;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tracing::debug;
+
+// StableSwap math works on balances normalized to 18 decimals
+const NORMALIZED_DECIMALS: u64 = 18;
+
+fn coin_index(coins: &[Address; 2], token: &Token) -> Result<usize> {
+    coins
+        .iter()
+        .position(|coin| *coin == token.address)
+        .ok_or_else(|| anyhow!("{} is not a coin of the Curve pool", token.symbol))
+}
+
+#[tracing::instrument(skip_all, fields(venue = "curve", pair = %token_pair.symbol))]
+pub async fn get_swap_details<M: Middleware + 'static>(
+    config: &Config,
+    provider: &Arc<M>,
+    token_pair: &TokenPair,
+    pool_address: Address,
+    coins: &[Address; 2],
+) -> Result<(Decimal, Decimal, Decimal, Vec<Address>)> {
+    let pool = CurveStableSwap::new(pool_address, provider.clone());
+
+    // Make sure the configured coin order matches the pool
+    for (index, coin) in coins.iter().enumerate() {
+        let pool_coin = pool.coins(U256::from(index)).call().await?;
+        if pool_coin != *coin {
+            bail!(
+                "Curve pool coin {index} is {pool_coin:?}, but {coin:?} was configured for {}",
+                token_pair.symbol
+            );
+        }
+    }
+
+    let amp = pool.a().call().await? * U256::from(A_PRECISION);
+    let fee = pool.fee().call().await?;
+
+    // Normalize balances in pool index order
+    let tokens = [&token_pair.token_0, &token_pair.token_1];
+    let mut xp = vec![U256::zero(); 2];
+    for token in tokens {
+        let index = coin_index(coins, token)?;
+        let balance = pool.balances(U256::from(index)).call().await?;
+        xp[index] = balance * U256::exp10((NORMALIZED_DECIMALS - token.decimals) as usize);
+    }
+    let stable_index = coin_index(coins, &token_pair.token_0)?;
+    let system_index = coin_index(coins, &token_pair.token_1)?;
+
+    // The system coin's price in terms of the stable coin
+    let d = get_d(&xp, amp)?;
+    let current_price = price_to_decimal(spot_price(system_index, stable_index, &xp, amp, d)?);
+    debug!(
+        pool = ?pool_address,
+        ?xp,
+        %amp,
+        %fee,
+        price = %current_price,
+        "Curve pool state"
+    );
+
+    // If we're within the allowed range, don't do any extra math
+    if decimal_is_within_allowed_range(current_price, config.ratio_range_allowed) {
+        return Ok((current_price, Decimal::ZERO, Decimal::ZERO, vec![]));
+    }
+
+    // Sell whichever coin is overpriced until its price falls to the target
+    let (i, j, target_price, token_to_sell, token_to_buy) = if current_price > Decimal::ONE {
+        (
+            system_index,
+            stable_index,
+            config.ratio_range_targets.1,
+            &token_pair.token_1,
+            &token_pair.token_0,
+        )
+    } else {
+        (
+            stable_index,
+            system_index,
+            Decimal::ONE / config.ratio_range_targets.0,
+            &token_pair.token_0,
+            &token_pair.token_1,
+        )
+    };
+    // Size the purchase on the fee-less curve, then ask how much has to go in once fees are taken
+    let dx_without_fee = dx_to_reach_price(i, j, &xp, amp, target_price)?;
+    let dy = get_dy(i, j, dx_without_fee, &xp, amp, U256::zero())?;
+    let dx = get_dx(i, j, dy, &xp, amp, fee)?;
+
+    let quantity_to_sell = u256_to_decimal(dx, NORMALIZED_DECIMALS);
+    let quantity_to_buy = u256_to_decimal(dy, NORMALIZED_DECIMALS);
+    let path = vec![token_to_sell.address, token_to_buy.address];
+
+    debug!(%quantity_to_sell, %quantity_to_buy, ?path, "Curve swap sized");
+    Ok((current_price, quantity_to_sell, quantity_to_buy, path))
+}
+
+/**
+ * What the pool would give for the swap right now, fees included.
+ */
+pub async fn quote_amount_out<M: Middleware + 'static>(
+    provider: &Arc<M>,
+    pool_address: Address,
+    coins: &[Address; 2],
+    swap_details: &SwapDetails,
+) -> Result<Decimal> {
+    let pool = CurveStableSwap::new(pool_address, provider.clone());
+    let i = coin_index(coins, &swap_details.token_to_sell)?;
+    let j = coin_index(coins, &swap_details.token_to_buy)?;
+    let dx = decimal_to_u256(
+        swap_details.amount_to_sell,
+        swap_details.token_to_sell.decimals,
+    );
+    let dy = pool.get_dy(i as i128, j as i128, dx).call().await?;
+    Ok(u256_to_decimal(dy, swap_details.token_to_buy.decimals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::curve_stable_swap::{ACall, BalancesCall, CoinsCall, FeeCall};
+    use crate::test_utils::{test_config, MockChain};
+
+    const POOL: Address = Address::repeat_byte(0x42);
+
+    fn mock_pool(coins: [Address; 2], balances: [U256; 2]) -> Arc<MockChain> {
+        let chain = Arc::new(MockChain::default());
+        for index in 0..2 {
+            chain.on_call(
+                POOL,
+                CoinsCall {
+                    i: U256::from(index),
+                },
+                coins[index],
+            );
+            chain.on_call(
+                POOL,
+                BalancesCall {
+                    i: U256::from(index),
+                },
+                balances[index],
+            );
+        }
+        chain.on_any_call::<ACall>(POOL, U256::from(200));
+        chain.on_any_call::<FeeCall>(POOL, U256::from(4_000_000));
+        chain
+    }
+
+    #[tokio::test]
+    async fn misconfigured_coins_are_an_error() {
+        let config = test_config();
+        let token_pair = &config.token_pairs[0];
+        let coins = [token_pair.token_0.address, token_pair.token_1.address];
+        let balance = U256::exp10(24);
+        let chain = mock_pool([coins[1], coins[0]], [balance, balance]);
+
+        let result = get_swap_details(&config, &chain.provider(), token_pair, POOL, &coins).await;
+        assert!(result.is_err_and(|error| error.to_string().contains("Curve pool coin 0")));
+    }
+
+    #[tokio::test]
+    async fn emptied_pools_are_an_error() {
+        let config = test_config();
+        let token_pair = &config.token_pairs[0];
+        let coins = [token_pair.token_0.address, token_pair.token_1.address];
+        let chain = mock_pool(coins, [U256::exp10(24), U256::zero()]);
+
+        let result = get_swap_details(&config, &chain.provider(), token_pair, POOL, &coins).await;
+        assert!(result.is_err());
+    }
+}
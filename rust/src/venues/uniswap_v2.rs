@@ -0,0 +1,213 @@
+use crate::config::Config;
+use crate::routing::{scaled_to_token_amount, RouteFinder};
+use crate::types::provider::UniswapRouter;
+use crate::types::swap::SwapDetails;
+use crate::types::token::{Token, TokenPair};
+use crate::utils::constant_product::{amount_in_to_reach_price, route_price, simulate_route};
+use crate::utils::decimal::{decimal_is_within_allowed_range, decimal_to_u256, u256_to_decimal};
+use anyhow::{anyhow, Result};
+use ethers::abi::Address;
+use ethers::providers::Middleware;
+use ethers::types::U256;
+use rust_decimal::{Decimal, MathematicalOps};
+use std::sync::Arc;
+use tracing::{debug, info_span, warn, Instrument};
+
+/**
+ * Asks the router what each candidate path costs for `amount_out`, returning the cheapest path and its amount in.
+ */
+async fn find_cheapest_path<M: Middleware + 'static>(
+    uniswap_router: &UniswapRouter<M>,
+    route_finder: &RouteFinder,
+    token_to_sell: &Token,
+    token_to_buy: &Token,
+    amount_out: U256,
+) -> Option<(Vec<Address>, U256)> {
+    let mut cheapest: Option<(Vec<Address>, U256)> = None;
+    for path in route_finder.candidate_paths(token_to_sell.address, token_to_buy.address) {
+        match uniswap_router
+            .get_amounts_in(amount_out, path.clone())
+            .call()
+            .instrument(info_span!("get_amounts_in", ?path, %amount_out))
+            .await
+        {
+            Ok(amounts_in) => {
+                let amount_in = amounts_in[0];
+                debug!(%amount_out, ?path, %amount_in, "Uniswap quoted the path");
+                if cheapest
+                    .as_ref()
+                    .is_none_or(|(_, cheapest_amount_in)| amount_in < *cheapest_amount_in)
+                {
+                    cheapest = Some((path, amount_in));
+                }
+            }
+            Err(error) => warn!(?path, %error, "Unable to quote the path"),
+        }
+    }
+    cheapest
+}
+
+/**
+ * How much to buy out of a direct pair holding `supply_0` of the stable coin and `supply_1` of the system coin to move
+ * its price towards `goal_ratio`.  The stable coin is bought when the system coin is worth more, the system coin
+ * otherwise.
+ */
+pub fn direct_quantity_to_buy(
+    supply_0: Decimal,
+    supply_1: Decimal,
+    goal_ratio: Decimal,
+) -> Decimal {
+    let total_supply = supply_0 + supply_1;
+    let expected_buy_token_supply = (total_supply / Decimal::TWO)
+        + (((goal_ratio - Decimal::ONE) / Decimal::TWO.powu(2)) * total_supply);
+    (supply_0 - expected_buy_token_supply).abs()
+}
+
+#[tracing::instrument(skip_all, fields(venue = "uniswap_v2", pair = %token_pair.symbol))]
+pub async fn get_swap_details<M: Middleware + 'static>(
+    config: &Config,
+    provider: &Arc<M>,
+    uniswap_router: &UniswapRouter<M>,
+    route_finder: &RouteFinder,
+    token_pair: &TokenPair,
+) -> Result<(Decimal, Decimal, Decimal, Vec<Address>)> {
+    // Price the system coin along the direct pair, or through the first intermediate token when there isn't one
+    let pricing_path = route_finder
+        .candidate_paths(token_pair.token_1.address, token_pair.token_0.address)
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No Uniswap V2 route exists for {}", token_pair.symbol))?;
+    let hops = route_finder.get_hops(provider, &pricing_path).await?;
+    let current_price = scaled_to_token_amount(route_price(&hops), token_pair.token_0.decimals)
+        / scaled_to_token_amount(Decimal::ONE, token_pair.token_1.decimals);
+
+    // If we're within the allowed range, don't do any extra math
+    if decimal_is_within_allowed_range(current_price, config.ratio_range_allowed) {
+        return Ok((current_price, Decimal::ZERO, Decimal::ZERO, vec![]));
+    }
+
+    let system_coin_is_worth_more = current_price > Decimal::ONE;
+
+    // Determine amount to buy/sell based on a goal ratio
+    let goal_ratio = if system_coin_is_worth_more {
+        config.ratio_range_targets.1
+    } else {
+        config.ratio_range_targets.0
+    };
+
+    let (quantity_to_buy, path_tokens) = if pricing_path.len() == 2 {
+        // Direct pair, reserves along the pricing path are system coin in and stable coin out
+        let supply_0 = scaled_to_token_amount(hops[0].reserve_out, token_pair.token_0.decimals);
+        let supply_1 = scaled_to_token_amount(hops[0].reserve_in, token_pair.token_1.decimals);
+        debug!(
+            reserve_0 = %supply_0,
+            reserve_1 = %supply_1,
+            price = %current_price,
+            "Reserve balances"
+        );
+
+        let quantity_to_buy = direct_quantity_to_buy(supply_0, supply_1, goal_ratio);
+        let path_tokens = if system_coin_is_worth_more {
+            vec![token_pair.token_1.clone(), token_pair.token_0.clone()]
+        } else {
+            vec![token_pair.token_0.clone(), token_pair.token_1.clone()]
+        };
+        let outcome_ratio = (supply_0 + quantity_to_buy) / (supply_1 + quantity_to_buy);
+        debug!(%quantity_to_buy, %outcome_ratio, "Direct swap sized");
+        (quantity_to_buy, path_tokens)
+    } else {
+        // Routed, size against the whole route until its marginal rate reaches the goal
+        let (path, hops, target_price, path_tokens) = if system_coin_is_worth_more {
+            let path_tokens = vec![token_pair.token_1.clone(), token_pair.token_0.clone()];
+            let target_price = goal_ratio
+                * scaled_to_token_amount(Decimal::ONE, token_pair.token_1.decimals)
+                / scaled_to_token_amount(Decimal::ONE, token_pair.token_0.decimals);
+            (pricing_path, hops, target_price, path_tokens)
+        } else {
+            let path: Vec<Address> = pricing_path.into_iter().rev().collect();
+            let hops = route_finder.get_hops(provider, &path).await?;
+            let path_tokens = vec![token_pair.token_0.clone(), token_pair.token_1.clone()];
+            let target_price = (Decimal::ONE / goal_ratio)
+                * scaled_to_token_amount(Decimal::ONE, token_pair.token_0.decimals)
+                / scaled_to_token_amount(Decimal::ONE, token_pair.token_1.decimals);
+            (path, hops, target_price, path_tokens)
+        };
+        let amount_in = amount_in_to_reach_price(&hops, config.uniswap_fee_rate, target_price);
+        let (amount_out, _) = simulate_route(amount_in, &hops, config.uniswap_fee_rate);
+        let quantity_to_buy = scaled_to_token_amount(amount_out, path_tokens[1].decimals);
+        debug!(route = ?path, %quantity_to_buy, "Routed swap sized");
+        (quantity_to_buy, path_tokens)
+    };
+
+    // Determine how many tokens need to be sold to achieve this purchase amount by asking Uniswap about every path
+    let amount_out = decimal_to_u256(quantity_to_buy, path_tokens[1].decimals);
+    let Some((path, amount_in_raw)) = find_cheapest_path(
+        uniswap_router,
+        route_finder,
+        &path_tokens[0],
+        &path_tokens[1],
+        amount_out,
+    )
+    .await
+    else {
+        warn!(pair = %token_pair.symbol, "No path could be quoted");
+        return Ok((current_price, Decimal::ZERO, Decimal::ZERO, vec![]));
+    };
+    let quantity_to_sell = u256_to_decimal(amount_in_raw, path_tokens[0].decimals);
+
+    debug!(
+        %quantity_to_sell,
+        %quantity_to_buy,
+        ?path,
+        fee_rate = %config.uniswap_fee_rate,
+        "Cheapest path"
+    );
+    Ok((current_price, quantity_to_sell, quantity_to_buy, path))
+}
+
+/**
+ * The pair's price once the swap has gone through its path, quoted the same way `get_swap_details` quotes it.
+ */
+pub async fn price_after_swap<M: Middleware + 'static>(
+    config: &Config,
+    provider: &Arc<M>,
+    route_finder: &RouteFinder,
+    token_pair: &TokenPair,
+    swap_details: &SwapDetails,
+) -> Result<Decimal> {
+    let hops = route_finder.get_hops(provider, &swap_details.path).await?;
+    let amount_in = swap_details.amount_to_sell
+        / scaled_to_token_amount(Decimal::ONE, swap_details.token_to_sell.decimals);
+    let (_, hops_after) = simulate_route(amount_in, &hops, config.uniswap_fee_rate);
+    // Prices are of the system coin, so swaps buying it run against the price
+    let price_after = if swap_details.token_to_sell.address == token_pair.token_1.address {
+        route_price(&hops_after)
+    } else {
+        Decimal::ONE / route_price(&hops_after)
+    };
+    Ok(
+        scaled_to_token_amount(price_after, token_pair.token_0.decimals)
+            / scaled_to_token_amount(Decimal::ONE, token_pair.token_1.decimals),
+    )
+}
+
+/**
+ * What the router would give for the swap right now, along its path.
+ */
+pub async fn quote_amount_out<M: Middleware + 'static>(
+    uniswap_router: &UniswapRouter<M>,
+    swap_details: &SwapDetails,
+) -> Result<Decimal> {
+    let amount_in = decimal_to_u256(
+        swap_details.amount_to_sell,
+        swap_details.token_to_sell.decimals,
+    );
+    let amounts_out = uniswap_router
+        .get_amounts_out(amount_in, swap_details.path.clone())
+        .call()
+        .await?;
+    Ok(u256_to_decimal(
+        *amounts_out.last().unwrap(),
+        swap_details.token_to_buy.decimals,
+    ))
+}
@@ -0,0 +1,244 @@
+use crate::config::Config;
+use crate::contracts::uniswap_v3_pool::UniswapV3Pool;
+use crate::contracts::uniswap_v3_quoter_v2::QuoteExactInputSingleParams;
+use crate::types::provider::{UniswapV3PoolFactory, UniswapV3Quoter};
+use crate::types::swap::SwapDetails;
+use crate::types::token::TokenPair;
+use crate::utils::decimal::{decimal_is_within_allowed_range, decimal_to_u256, u256_to_decimal};
+use crate::utils::uniswap_v3::{
+    amounts_to_reach_sqrt_price, sqrt_price_x96_to_decimal, tick_at_sqrt_price,
+    tick_boundaries_between,
+};
+use anyhow::{anyhow, bail, Result};
+use ethers::abi::Address;
+use ethers::providers::Middleware;
+use ethers::types::U256;
+use rust_decimal::{Decimal, MathematicalOps};
+use std::sync::Arc;
+use tracing::debug;
+
+// Uniswap V3 fees are expressed in hundredths of a basis point
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+#[tracing::instrument(skip_all, fields(venue = "uniswap_v3", pair = %token_pair.symbol))]
+pub async fn get_swap_details<M: Middleware + 'static>(
+    config: &Config,
+    provider: &Arc<M>,
+    uniswap_v3_factory: &UniswapV3PoolFactory<M>,
+    token_pair: &TokenPair,
+    fee: u32,
+) -> Result<(Decimal, Decimal, Decimal, Vec<Address>)> {
+    // Get the pool for this fee tier from the factory
+    let pool_address = uniswap_v3_factory
+        .get_pool(token_pair.token_0.address, token_pair.token_1.address, fee)
+        .call()
+        .await?;
+    if pool_address == Address::zero() {
+        bail!(
+            "No Uniswap V3 pool exists for {} with fee={fee}",
+            token_pair.symbol
+        );
+    }
+    let pool = UniswapV3Pool::new(pool_address, provider.clone());
+
+    let (sqrt_price_x96, current_tick, _, _, _, _, _) = pool.slot_0().call().await?;
+    let liquidity = pool.liquidity().call().await?;
+    let tick_spacing = pool.tick_spacing().call().await?;
+    let pool_token_0 = pool.token_0().call().await?;
+
+    // The pool orders tokens by address, our price is always token_1 (system coin) in terms of token_0
+    let system_coin_is_pool_token_0 = pool_token_0 == token_pair.token_1.address;
+    let (pool_token_0, pool_token_1) = if system_coin_is_pool_token_0 {
+        (&token_pair.token_1, &token_pair.token_0)
+    } else {
+        (&token_pair.token_0, &token_pair.token_1)
+    };
+    let decimals_adjustment = Decimal::TEN.checked_powu(pool_token_0.decimals).unwrap()
+        / Decimal::TEN.checked_powu(pool_token_1.decimals).unwrap();
+
+    let sqrt_price_current = sqrt_price_x96_to_decimal(sqrt_price_x96)?;
+    let pool_price = sqrt_price_current * sqrt_price_current * decimals_adjustment;
+    let current_price = if system_coin_is_pool_token_0 {
+        pool_price
+    } else {
+        Decimal::ONE / pool_price
+    };
+    debug!(
+        pool = ?pool_address,
+        sqrt_price = %sqrt_price_current,
+        tick = current_tick,
+        liquidity,
+        price = %current_price,
+        "Pool state"
+    );
+
+    // If we're within the allowed range, don't do any extra math
+    if decimal_is_within_allowed_range(current_price, config.ratio_range_allowed) {
+        return Ok((current_price, Decimal::ZERO, Decimal::ZERO, vec![]));
+    }
+
+    // Determine the price to move towards, in the pool's own raw orientation
+    let goal_ratio = if current_price > Decimal::ONE {
+        config.ratio_range_targets.1
+    } else {
+        config.ratio_range_targets.0
+    };
+    let target_pool_price = if system_coin_is_pool_token_0 {
+        goal_ratio
+    } else {
+        Decimal::ONE / goal_ratio
+    };
+    let sqrt_price_target = (target_pool_price / decimals_adjustment)
+        .sqrt()
+        .ok_or_else(|| anyhow!("No sqrt price for a target of {target_pool_price}"))?;
+    let target_tick = tick_at_sqrt_price(sqrt_price_target);
+
+    // Find the liquidity changes between here and the target, a walk cut short would misquote the swap
+    let max_tick_boundaries = config
+        .uniswap_v3
+        .as_ref()
+        .ok_or_else(|| anyhow!("Uniswap V3 pair configured without Uniswap V3 settings"))?
+        .max_tick_boundaries;
+    let boundaries = tick_boundaries_between(current_tick, target_tick, tick_spacing);
+    if boundaries.len() > max_tick_boundaries {
+        bail!(
+            "Target tick={target_tick} is {} boundaries away from tick={current_tick}, more than the {max_tick_boundaries} inspected",
+            boundaries.len()
+        );
+    }
+    let mut initialized_ticks = vec![];
+    for boundary in boundaries {
+        let (_, liquidity_net, _, _, _, _, _, initialized) = pool.ticks(boundary).call().await?;
+        if initialized {
+            initialized_ticks.push((boundary, liquidity_net));
+        }
+    }
+
+    let (amount_0, amount_1) = amounts_to_reach_sqrt_price(
+        sqrt_price_current,
+        sqrt_price_target,
+        liquidity,
+        &initialized_ticks,
+    )?;
+
+    // Price going down means token0 goes in and token1 comes out
+    let zero_for_one = sqrt_price_target < sqrt_price_current;
+    let (token_to_sell, token_to_buy, raw_amount_in, raw_amount_out) = if zero_for_one {
+        (pool_token_0, pool_token_1, amount_0, amount_1)
+    } else {
+        (pool_token_1, pool_token_0, amount_1, amount_0)
+    };
+    let fee_rate = Decimal::from(fee) / Decimal::from(FEE_DENOMINATOR);
+    let raw_amount_in = raw_amount_in / (Decimal::ONE - fee_rate);
+
+    let quantity_to_sell =
+        raw_amount_in / Decimal::TEN.checked_powu(token_to_sell.decimals).unwrap();
+    let quantity_to_buy =
+        raw_amount_out / Decimal::TEN.checked_powu(token_to_buy.decimals).unwrap();
+    let path = vec![token_to_sell.address, token_to_buy.address];
+
+    debug!(
+        target_tick,
+        ticks_crossed = initialized_ticks.len(),
+        %quantity_to_sell,
+        %quantity_to_buy,
+        ?path,
+        "Uniswap V3 swap sized"
+    );
+    Ok((current_price, quantity_to_sell, quantity_to_buy, path))
+}
+
+/**
+ * What the pool would give for the swap right now, as QuoterV2 simulates it through an `eth_call`.
+ */
+pub async fn quote_amount_out<M: Middleware + 'static>(
+    uniswap_v3_quoter: &UniswapV3Quoter<M>,
+    fee: u32,
+    swap_details: &SwapDetails,
+) -> Result<Decimal> {
+    let (amount_out, _, _, _) = uniswap_v3_quoter
+        .quote_exact_input_single(QuoteExactInputSingleParams {
+            token_in: swap_details.token_to_sell.address,
+            token_out: swap_details.token_to_buy.address,
+            amount_in: decimal_to_u256(
+                swap_details.amount_to_sell,
+                swap_details.token_to_sell.decimals,
+            ),
+            fee,
+            sqrt_price_limit_x96: U256::zero(),
+        })
+        .call()
+        .await?;
+    Ok(u256_to_decimal(
+        amount_out,
+        swap_details.token_to_buy.decimals,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UniswapV3Config;
+    use crate::contracts::uniswap_v3_factory::{GetPoolCall, UniswapV3Factory};
+    use crate::contracts::uniswap_v3_pool::{
+        LiquidityCall, Slot0Call, Slot0Return, TickSpacingCall, Token0Call,
+    };
+    use crate::test_utils::{test_config, MockChain};
+
+    const FACTORY: Address = Address::repeat_byte(0x12);
+    const POOL: Address = Address::repeat_byte(0x41);
+
+    #[tokio::test]
+    async fn missing_pools_are_an_error() {
+        let config = test_config();
+        let chain = Arc::new(MockChain::default());
+        chain.on_any_call::<GetPoolCall>(FACTORY, Address::zero());
+        let factory = UniswapV3Factory::new(FACTORY, chain.provider());
+
+        let result = get_swap_details(
+            &config,
+            &chain.provider(),
+            &factory,
+            &config.token_pairs[0],
+            500,
+        )
+        .await;
+        assert!(result.is_err_and(|error| error.to_string().contains("No Uniswap V3 pool")));
+    }
+
+    #[tokio::test]
+    async fn targets_past_the_inspected_ticks_are_an_error() {
+        let mut config = test_config();
+        config.uniswap_v3 = Some(UniswapV3Config {
+            factory_address: FACTORY,
+            router_address: Address::repeat_byte(0x14),
+            quoter_address: Address::repeat_byte(0x13),
+            adapter_name: String::from("USDC_V3"),
+            adapter_address: Address::repeat_byte(0x31),
+            max_tick_boundaries: 100,
+        });
+        let token_pair = &config.token_pairs[0];
+        let chain = Arc::new(MockChain::default());
+        chain.on_any_call::<GetPoolCall>(FACTORY, POOL);
+        // ZAI at 1.05 USDC, some 490 single ticks from the 1.001 target
+        chain.on_any_call::<Slot0Call>(
+            POOL,
+            Slot0Return {
+                sqrt_price_x96: (U256::one() << 96) * 9759 / 10000,
+                tick: -488,
+                observation_index: 0,
+                observation_cardinality: 1,
+                observation_cardinality_next: 1,
+                fee_protocol: 0,
+                unlocked: true,
+            },
+        );
+        chain.on_any_call::<LiquidityCall>(POOL, 1_000_000_000_000_000_000_000u128);
+        chain.on_any_call::<TickSpacingCall>(POOL, 1i32);
+        chain.on_any_call::<Token0Call>(POOL, token_pair.token_0.address);
+        let factory = UniswapV3Factory::new(FACTORY, chain.provider());
+
+        let result = get_swap_details(&config, &chain.provider(), &factory, token_pair, 100).await;
+        assert!(result.is_err_and(|error| error.to_string().contains("boundaries away")));
+    }
+}
@@ -0,0 +1,299 @@
+//! End-to-end run of the keeper against a local Anvil chain.
+//!
+//! The test deploys its own fixtures from forge artifacts (`abi` plus `bytecode`) under `abis/`, and fails when one
+//! of them only holds an ABI:
+//!
+//! - `MockERC20`, an ERC-20 taking `(name, symbol, decimals)` with an open `mint(to, amount)`, for USDC and ZAI
+//! - `UniswapV2Factory` and `UniswapV2Router02`, the canonical builds so the router's pair init code hash matches
+//! - `AzosAdapterUniswapV2`, taking the router's address
+//! - `AzosStabilityModule`
+//!
+//! ```shell
+//! cargo test --test anvil -- --ignored
+//! ```
+
+use azos_keeper::config::{AdapterConfig, AdapterKind, Config, SignerConfig};
+use azos_keeper::contracts::azos_stability_module::{AzosStabilityModule, ExpandFilter};
+use azos_keeper::contracts::uniswap_v2_factory::UniswapV2Factory;
+use azos_keeper::contracts::uniswap_v2_pair::UniswapV2Pair;
+use azos_keeper::contracts::uniswap_v2_router02::UniswapV2Router02;
+use azos_keeper::types::token::{Token, TokenPair};
+use azos_keeper::types::venue::{PairVenue, Venue, VenueSelection};
+use azos_keeper::Keeper;
+use ethers::abi::{Abi, Tokenize};
+use ethers::prelude::*;
+use ethers::utils::{format_bytes32_string, parse_units, Anvil, AnvilInstance};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+abigen!(
+    MockErc20,
+    r#"[
+        function approve(address spender, uint256 amount) external returns (bool)
+        function mint(address to, uint256 amount) external
+    ]"#
+);
+
+type AnvilProvider = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+const ADAPTER_NAME: &str = "UNISWAP_V2";
+
+/// Where the fixtures landed on the chain
+struct Deployment {
+    usdc: Address,
+    zai: Address,
+    uniswap_factory: Address,
+    uniswap_router: Address,
+    stability_module: Address,
+    adapter_uniswap_v2: Address,
+}
+
+/**
+ * Deploys the forge artifact `abis/<name>.json`, panicking when it has no bytecode to deploy.
+ */
+async fn deploy<T: Tokenize>(name: &str, provider: &Arc<AnvilProvider>, args: T) -> Address {
+    let path = format!("{}/abis/{name}.json", env!("CARGO_MANIFEST_DIR"));
+    let artifact: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&path)
+            .unwrap_or_else(|error| panic!("Can't read {path}: {error}")),
+    )
+    .unwrap();
+    let bytecode = artifact["bytecode"]["object"]
+        .as_str()
+        .or_else(|| artifact["bytecode"].as_str())
+        .unwrap_or_else(|| panic!("{path} has no bytecode, replace it with the compiled artifact"));
+    let abi: Abi = serde_json::from_value(artifact["abi"].clone()).unwrap();
+    ContractFactory::new(abi, bytecode.parse().unwrap(), provider.clone())
+        .deploy(args)
+        .unwrap_or_else(|error| panic!("Can't deploy {name}: {error}"))
+        .send()
+        .await
+        .unwrap_or_else(|error| panic!("Can't deploy {name}: {error}"))
+        .address()
+}
+
+/**
+ * Deploys the tokens, a USDC/ZAI pair holding 100,000 of each, the adapter and the stability module.  The deployer
+ * keeps 900,000 of each token.
+ */
+async fn deploy_fixtures(provider: &Arc<AnvilProvider>) -> Deployment {
+    let deployer = provider.address();
+    let supply: U256 = parse_units("1000000", 18).unwrap().into();
+    let liquidity: U256 = parse_units("100000", 18).unwrap().into();
+
+    let usdc = deploy(
+        "MockERC20",
+        provider,
+        (String::from("USD Coin"), String::from("USDC"), 18u8),
+    )
+    .await;
+    let zai = deploy(
+        "MockERC20",
+        provider,
+        (String::from("Azos ZAI"), String::from("ZAI"), 18u8),
+    )
+    .await;
+    let uniswap_factory = deploy("UniswapV2Factory", provider, deployer).await;
+    // Only token to token swaps are made, so the router's WETH is never touched
+    let uniswap_router = deploy(
+        "UniswapV2Router02",
+        provider,
+        (uniswap_factory, Address::zero()),
+    )
+    .await;
+    let adapter_uniswap_v2 = deploy("AzosAdapterUniswapV2", provider, uniswap_router).await;
+    let stability_module = deploy(
+        "AzosStabilityModule",
+        provider,
+        (
+            usdc,
+            adapter_uniswap_v2,
+            format_bytes32_string(ADAPTER_NAME).unwrap(),
+            deployer,
+            zai,
+            deployer,
+            U256::MAX,
+            U256::MAX,
+            U256::zero(),
+        ),
+    )
+    .await;
+
+    for token in [usdc, zai] {
+        let token = MockErc20::new(token, provider.clone());
+        token
+            .mint(deployer, supply)
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+        token
+            .approve(uniswap_router, liquidity)
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+    }
+    UniswapV2Router02::new(uniswap_router, provider.clone())
+        .add_liquidity(
+            usdc,
+            zai,
+            liquidity,
+            liquidity,
+            liquidity,
+            liquidity,
+            deployer,
+            U256::MAX,
+        )
+        .send()
+        .await
+        .unwrap()
+        .await
+        .unwrap();
+
+    Deployment {
+        usdc,
+        zai,
+        uniswap_factory,
+        uniswap_router,
+        stability_module,
+        adapter_uniswap_v2,
+    }
+}
+
+fn keeper_config(anvil: &AnvilInstance, deployment: &Deployment) -> Config {
+    let token = |symbol: &str, address: Address| Token {
+        symbol: String::from(symbol),
+        address,
+        decimals: 18,
+    };
+    Config {
+        rpc_urls: vec![anvil.endpoint()],
+        rpc_quorum: None,
+        delay_between_checks_ms: 100,
+        token_pairs: vec![TokenPair {
+            symbol: String::from("USDC/ZAI"),
+            token_0: token("USDC", deployment.usdc),
+            token_1: token("ZAI", deployment.zai),
+            venues: vec![PairVenue {
+                venue: Venue::UniswapV2,
+                adapter_name: String::from(ADAPTER_NAME),
+            }],
+            slippage_bps: 50,
+        }],
+        keeper_signer: SignerConfig::PrivateKey(ethers::utils::hex::encode(
+            anvil.keys()[0].to_bytes(),
+        )),
+        uniswap_router_address: deployment.uniswap_router,
+        uniswap_factory_address: deployment.uniswap_factory,
+        uniswap_fee_rate: Decimal::from_str_exact("0.003").unwrap(),
+        stability_module_address: deployment.stability_module,
+        ratio_range_allowed: (
+            Decimal::from_str_exact("0.996").unwrap(),
+            Decimal::from_str_exact("1.002").unwrap(),
+        ),
+        ratio_range_targets: (
+            Decimal::from_str_exact("0.997").unwrap(),
+            Decimal::from_str_exact("1.001").unwrap(),
+        ),
+        adapters: vec![AdapterConfig {
+            name: String::from(ADAPTER_NAME),
+            address: deployment.adapter_uniswap_v2,
+            kind: AdapterKind::UniswapV2 {
+                router_address: deployment.uniswap_router,
+            },
+        }],
+        tx_confirmations_required: 1,
+        uniswap_v3: None,
+        routing_tokens: vec![],
+        route_discovery_max_pairs: 0,
+        venue_selection: VenueSelection::Best,
+        swap_deadline_seconds: 120,
+        max_clock_skew_seconds: 30,
+        authorization_required: false,
+        bundle_relay: None,
+        alerts: None,
+    }
+}
+
+/**
+ * USDC per ZAI in the pair, both tokens having 18 decimals.
+ */
+async fn pool_price(provider: &Arc<AnvilProvider>, deployment: &Deployment) -> Decimal {
+    let factory = UniswapV2Factory::new(deployment.uniswap_factory, provider.clone());
+    let pair_address = factory
+        .get_pair(deployment.usdc, deployment.zai)
+        .call()
+        .await
+        .unwrap();
+    let pair = UniswapV2Pair::new(pair_address, provider.clone());
+    let (reserve_0, reserve_1, _) = pair.get_reserves().call().await.unwrap();
+    let (reserve_usdc, reserve_zai) = if pair.token_0().call().await.unwrap() == deployment.usdc {
+        (reserve_0, reserve_1)
+    } else {
+        (reserve_1, reserve_0)
+    };
+    Decimal::from(reserve_usdc) / Decimal::from(reserve_zai)
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and the fixture artifacts, see the module docs"]
+async fn keeper_pulls_an_expensive_zai_back_to_peg() {
+    let anvil = Anvil::new().spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone()).with_chain_id(anvil.chain_id());
+    let provider = Provider::<Http>::try_from(anvil.endpoint()).unwrap();
+    let provider = Arc::new(SignerMiddleware::new(provider, wallet.clone()));
+    let deployment = deploy_fixtures(&provider).await;
+
+    // Buy ZAI with USDC until it trades well above the allowed range
+    let amount_in: U256 = parse_units("20000", 18).unwrap().into();
+    MockErc20::new(deployment.usdc, provider.clone())
+        .approve(deployment.uniswap_router, amount_in)
+        .send()
+        .await
+        .unwrap()
+        .await
+        .unwrap();
+    let router = UniswapV2Router02::new(deployment.uniswap_router, provider.clone());
+    router
+        .swap_exact_tokens_for_tokens(
+            amount_in,
+            U256::zero(),
+            vec![deployment.usdc, deployment.zai],
+            wallet.address(),
+            U256::MAX,
+        )
+        .send()
+        .await
+        .unwrap()
+        .await
+        .unwrap();
+    let config = keeper_config(&anvil, &deployment);
+    let price_before = pool_price(&provider, &deployment).await;
+    assert!(price_before > config.ratio_range_allowed.1);
+
+    let mut keeper = Keeper::with_provider(config, provider.clone())
+        .await
+        .unwrap();
+    let block_number = provider.get_block_number().await.unwrap().as_u64();
+    keeper.tick(block_number).await.unwrap();
+
+    // The swap is sized with the pool's fee, so it lands inside the target band
+    let price_after = pool_price(&provider, &deployment).await;
+    let (low, high) = keeper.config().ratio_range_targets;
+    assert!(
+        price_after >= low && price_after <= high,
+        "price moved from {price_before} to {price_after}"
+    );
+
+    let stability_module = AzosStabilityModule::new(deployment.stability_module, provider.clone());
+    let expansions = stability_module
+        .event::<ExpandFilter>()
+        .from_block(block_number)
+        .query()
+        .await
+        .unwrap();
+    assert_eq!(expansions.len(), 1);
+}